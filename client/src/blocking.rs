@@ -0,0 +1,114 @@
+use super::{
+    clock_url, hint_url, move_url, plan_url, plans_url, snake_url, BoardState, ClientError,
+    Direction, Hint, MovePlan, PlanReceipt, ServerClock, DEFAULT_URL,
+};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Blocking client of the snake API, must not be used within an async runtime
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::blocking::Client,
+    base_url: String,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(reqwest::blocking::Client::new(), base_url)
+    }
+
+    pub fn with_http_client(http: reqwest::blocking::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into(),
+        }
+    }
+
+    /// Board rendered as text, exactly as `GET /api/v1/snake` returns it
+    pub fn board(&self) -> Result<String, ClientError> {
+        let response = self.http.get(snake_url(&self.base_url)).send()?;
+        Ok(ClientError::check_blocking(response)?.text()?)
+    }
+
+    pub fn state(&self) -> Result<BoardState, ClientError> {
+        let response = self
+            .http
+            .get(snake_url(&self.base_url))
+            .query(&[("format", "json")])
+            .send()?;
+        Ok(ClientError::check_blocking(response)?.json()?)
+    }
+
+    pub fn issue_move(&self, direction: Direction) -> Result<(), ClientError> {
+        let response = self.http.post(move_url(&self.base_url, direction)).send()?;
+        ClientError::check_blocking(response)?;
+
+        Ok(())
+    }
+
+    /// Server time and tick timing, for sending moves in step with the ticks
+    pub fn clock(&self) -> Result<ServerClock, ClientError> {
+        let response = self.http.get(clock_url(&self.base_url)).send()?;
+        Ok(ClientError::check_blocking(response)?.json()?)
+    }
+
+    /// Safe directions and the shortest path to a fruit, computed from the latest board
+    pub fn hint(&self) -> Result<Hint, ClientError> {
+        let response = self.http.get(hint_url(&self.base_url)).send()?;
+        Ok(ClientError::check_blocking(response)?.json()?)
+    }
+
+    /// Schedules moves to be made one per tick, replacing the previous plan of this client
+    pub fn plan_moves(&self, plan: &MovePlan) -> Result<PlanReceipt, ClientError> {
+        let response = self
+            .http
+            .post(plans_url(&self.base_url))
+            .json(plan)
+            .send()?;
+        Ok(ClientError::check_blocking(response)?.json()?)
+    }
+
+    pub fn cancel_plan(&self, id: u64) -> Result<(), ClientError> {
+        let response = self.http.delete(plan_url(&self.base_url, id)).send()?;
+        ClientError::check_blocking(response)?;
+
+        Ok(())
+    }
+
+    /// Endless iterator polling the board every `every`, the first one is fetched immediately
+    pub fn frames(&self, every: Duration) -> Frames<'_> {
+        Frames {
+            client: self,
+            every,
+            last_poll: None,
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(DEFAULT_URL)
+    }
+}
+
+#[derive(Debug)]
+pub struct Frames<'a> {
+    client: &'a Client,
+    every: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Result<String, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(last_poll) = self.last_poll {
+            // Time spent by the caller between polls counts towards the interval
+            thread::sleep(self.every.saturating_sub(last_poll.elapsed()));
+        }
+        self.last_poll = Some(Instant::now());
+
+        Some(self.client.board())
+    }
+}
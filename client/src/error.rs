@@ -0,0 +1,101 @@
+use snake_proto::Problem;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("Request to the snake server failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Snake server responded with {status}: {message}")]
+    Server {
+        status: reqwest::StatusCode,
+        /// One of `snake_proto::error_code`, unless the response was not a `Problem`
+        code: Option<String>,
+        message: String,
+    },
+}
+
+impl ClientError {
+    fn server(status: reqwest::StatusCode, body: String) -> Self {
+        match serde_json::from_str::<Problem>(&body) {
+            Ok(problem) => ClientError::Server {
+                status,
+                code: Some(problem.code),
+                message: problem.detail,
+            },
+            Err(_) => ClientError::Server {
+                status,
+                code: None,
+                message: body,
+            },
+        }
+    }
+
+    /// Machine-readable code of the error reported by the server
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Server { code, .. } => code.as_deref(),
+            ClientError::Http(_) => None,
+        }
+    }
+
+    pub(crate) async fn check(
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response, ClientError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        Err(ClientError::server(
+            status,
+            response.text().await.unwrap_or_default(),
+        ))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn check_blocking(
+        response: reqwest::blocking::Response,
+    ) -> Result<reqwest::blocking::Response, ClientError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        Err(ClientError::server(
+            status,
+            response.text().unwrap_or_default(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use snake_proto::error_code;
+
+    #[test]
+    fn test_problem_body_is_parsed() {
+        let body = r#"{"type":"urn:snake:error:reverse_move_ignored","title":"Conflict","status":409,"detail":"Snake is moving up","code":"reverse_move_ignored"}"#;
+
+        let error = ClientError::server(reqwest::StatusCode::CONFLICT, body.to_string());
+
+        assert_eq!(error.code(), Some(error_code::REVERSE_MOVE_IGNORED));
+        assert_eq!(
+            error.to_string(),
+            "Snake server responded with 409 Conflict: Snake is moving up"
+        );
+    }
+
+    #[test]
+    fn test_plain_body_is_kept() {
+        let error = ClientError::server(reqwest::StatusCode::BAD_GATEWAY, "oops".to_string());
+
+        assert_eq!(error.code(), None);
+        assert_eq!(
+            error.to_string(),
+            "Snake server responded with 502 Bad Gateway: oops"
+        );
+    }
+}
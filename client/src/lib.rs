@@ -0,0 +1,177 @@
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod error;
+
+pub use error::ClientError;
+pub use snake_proto::{
+    error_code, BoardState, Direction, Edge, Hint, MovePlan, PlanReceipt, PlannedMove, Point,
+    Portal, ServerClock, Topology,
+};
+
+use std::time::Duration;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+pub const DEFAULT_URL: &str = "http://localhost:8080";
+
+fn snake_url(base_url: &str) -> String {
+    format!("{}/api/v1/snake", base_url.trim_end_matches('/'))
+}
+
+fn move_url(base_url: &str, direction: Direction) -> String {
+    format!("{}/{direction}", snake_url(base_url))
+}
+
+fn clock_url(base_url: &str) -> String {
+    format!("{}/clock", snake_url(base_url))
+}
+
+fn hint_url(base_url: &str) -> String {
+    format!("{}/hint", snake_url(base_url))
+}
+
+fn plans_url(base_url: &str) -> String {
+    format!("{}/plans", snake_url(base_url))
+}
+
+fn plan_url(base_url: &str, id: u64) -> String {
+    format!("{}/{id}", plans_url(base_url))
+}
+
+/// Asynchronous client of the snake API
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url)
+    }
+
+    pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into(),
+        }
+    }
+
+    /// Board rendered as text, exactly as `GET /api/v1/snake` returns it
+    pub async fn board(&self) -> Result<String, ClientError> {
+        let response = self.http.get(snake_url(&self.base_url)).send().await?;
+        Ok(ClientError::check(response).await?.text().await?)
+    }
+
+    pub async fn state(&self) -> Result<BoardState, ClientError> {
+        let response = self
+            .http
+            .get(snake_url(&self.base_url))
+            .query(&[("format", "json")])
+            .send()
+            .await?;
+        Ok(ClientError::check(response).await?.json().await?)
+    }
+
+    pub async fn issue_move(&self, direction: Direction) -> Result<(), ClientError> {
+        let response = self
+            .http
+            .post(move_url(&self.base_url, direction))
+            .send()
+            .await?;
+        ClientError::check(response).await?;
+
+        Ok(())
+    }
+
+    /// Server time and tick timing, for sending moves in step with the ticks
+    pub async fn clock(&self) -> Result<ServerClock, ClientError> {
+        let response = self.http.get(clock_url(&self.base_url)).send().await?;
+        Ok(ClientError::check(response).await?.json().await?)
+    }
+
+    /// Safe directions and the shortest path to a fruit, computed from the latest board
+    pub async fn hint(&self) -> Result<Hint, ClientError> {
+        let response = self.http.get(hint_url(&self.base_url)).send().await?;
+        Ok(ClientError::check(response).await?.json().await?)
+    }
+
+    /// Schedules moves to be made one per tick, replacing the previous plan of this client
+    pub async fn plan_moves(&self, plan: &MovePlan) -> Result<PlanReceipt, ClientError> {
+        let response = self
+            .http
+            .post(plans_url(&self.base_url))
+            .json(plan)
+            .send()
+            .await?;
+        Ok(ClientError::check(response).await?.json().await?)
+    }
+
+    pub async fn cancel_plan(&self, id: u64) -> Result<(), ClientError> {
+        let response = self
+            .http
+            .delete(plan_url(&self.base_url, id))
+            .send()
+            .await?;
+        ClientError::check(response).await?;
+
+        Ok(())
+    }
+
+    /// Polls the board every `every`, see `Frames::next`
+    pub fn frames(&self, every: Duration) -> Frames {
+        let mut interval = interval(every);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Frames {
+            client: self.clone(),
+            interval,
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(DEFAULT_URL)
+    }
+}
+
+/// Polling subscription to the text board
+#[derive(Debug)]
+pub struct Frames {
+    client: Client,
+    interval: Interval,
+}
+
+impl Frames {
+    /// Waits for the next poll and fetches the board, the first one is fetched immediately
+    pub async fn next(&mut self) -> Result<String, ClientError> {
+        self.interval.tick().await;
+        self.client.board().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_move_url_ignores_trailing_slash() {
+        assert_eq!(
+            move_url("http://localhost:8080/", Direction::Left),
+            "http://localhost:8080/api/v1/snake/left"
+        );
+        assert_eq!(
+            move_url(DEFAULT_URL, Direction::Up),
+            "http://localhost:8080/api/v1/snake/up"
+        );
+    }
+
+    #[test]
+    fn test_plan_url() {
+        assert_eq!(
+            plan_url(DEFAULT_URL, 7),
+            "http://localhost:8080/api/v1/snake/plans/7"
+        );
+    }
+}
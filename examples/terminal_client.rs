@@ -4,31 +4,35 @@ use std::sync::mpsc;
 use std::thread;
 use std::time;
 
-use reqwest::blocking::Client;
+use snake_client::blocking::Client;
+use snake_client::Direction;
 use termion::event::Key::Char;
 use termion::input::TermRead;
 use termion::raw::IntoRawMode;
 
-const URL: &str = "http://localhost:8080/snake";
-
 fn main() {
     let mut stdout = io::stdout().into_raw_mode().unwrap();
 
     let mut stdin = termion::async_stdin().keys();
 
-    let client = Client::new();
+    let client = Client::default();
 
-    let (tx, rx): (mpsc::Sender<&str>, mpsc::Receiver<&str>) = mpsc::channel();
+    let (tx, rx): (mpsc::Sender<Direction>, mpsc::Receiver<Direction>) = mpsc::channel();
     let post_client = client.clone();
     let handle = thread::spawn(move || {
         while let Ok(dir) = rx.recv() {
-            post_client.post(format!("{URL}/{}", dir)).send().unwrap();
+            // Moves are rejected now and then, e.g. a second key within a tick or reversing the
+            // snake, which is of no consequence to the game
+            let _ = post_client.issue_move(dir);
         }
     });
 
-    loop {
-        let mut game_text = client.get(URL).send().unwrap().text().unwrap();
-        game_text = game_text.replace('\n', "\r\n");
+    for game_text in client.frames(time::Duration::from_millis(50)) {
+        // Keep polling through errors, the server may be restarting
+        let game_text = match game_text {
+            Ok(game_text) => game_text.replace('\n', "\r\n"),
+            Err(e) => format!("Waiting for the server: {e}\r\n"),
+        };
 
         write!(
             stdout,
@@ -47,16 +51,17 @@ fn main() {
         if let Some(Ok(key)) = input {
             if let Some(dir) = match key {
                 Char('q') => break,
-                Char('l') => Some("right"),
-                Char('k') => Some("up"),
-                Char('j') => Some("down"),
-                Char('h') => Some("left"),
+                Char('l') => Some(Direction::Right),
+                Char('k') => Some(Direction::Up),
+                Char('j') => Some(Direction::Down),
+                Char('h') => Some(Direction::Left),
                 _ => None,
             } {
-                tx.send(dir).unwrap();
+                if tx.send(dir).is_err() {
+                    break;
+                }
             }
         }
-        thread::sleep(time::Duration::from_millis(50));
     }
     drop(tx);
 
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// Response header carrying the tick of the returned board
+pub const TICK_HEADER: &str = "tick";
+/// Response header carrying the milliseconds left until the next tick resolves
+pub const NEXT_TICK_IN_MS_HEADER: &str = "next-tick-in-ms";
+
+/// Server's view of time, for clients aligning their moves with tick boundaries
+///
+/// Estimating the offset to the server clock works like NTP, taking half of the round trip
+/// as the time the response spent on its way back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ServerClock {
+    /// Milliseconds since the Unix epoch, when the response was made
+    pub server_time_ms: u64,
+    /// Tick of the latest board
+    pub tick: u64,
+    /// Time left until the next tick resolves, missing before the game has started
+    pub next_tick_in_ms: Option<u64>,
+    /// Time between two ticks, missing before the game has started
+    pub tick_period_ms: Option<u64>,
+}
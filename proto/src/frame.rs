@@ -0,0 +1,390 @@
+use super::point::Point;
+use super::state::{BoardState, Portal};
+use super::topology::Topology;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Content of a single board cell, the border around the board is implied by its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Cell {
+    Empty,
+    Snake,
+    Head,
+    Fruit,
+    /// Wall put up inside the board, the snake dies running into it
+    Wall,
+    /// Either end of the pair of portals with the given number
+    Portal(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CellChange {
+    pub x: u16,
+    pub y: u16,
+    pub cell: Cell,
+}
+
+/// Complete board at a given tick, cells are stored row by row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Frame {
+    pub tick: u64,
+    pub width: u16,
+    pub height: u16,
+    pub score: u32,
+    pub cells: Vec<Cell>,
+}
+
+/// Cells which changed between the frame at `base_tick` and the one at `tick`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Delta {
+    pub tick: u64,
+    pub base_tick: u64,
+    pub score: u32,
+    pub changes: Vec<CellChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrameUpdate {
+    Keyframe(Frame),
+    Delta(Delta),
+}
+
+impl FrameUpdate {
+    pub fn tick(&self) -> u64 {
+        match self {
+            Self::Keyframe(frame) => frame.tick,
+            Self::Delta(delta) => delta.tick,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DeltaError {
+    #[error("Delta is based on tick {expected}, but the frame is at tick {found}")]
+    BaseTickMismatch { expected: u64, found: u64 },
+
+    #[error("Cell ({x}, {y}) lays outside of the board")]
+    OutOfBounds { x: u16, y: u16 },
+
+    #[error("A delta cannot be applied before the first keyframe")]
+    MissingKeyframe,
+}
+
+impl Frame {
+    pub fn empty(tick: u64, width: u16, height: u16) -> Self {
+        Self {
+            tick,
+            width,
+            height,
+            score: 0,
+            cells: vec![Cell::Empty; width as usize * height as usize],
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Option<Cell> {
+        self.index(x, y).map(|i| self.cells[i])
+    }
+
+    pub fn set_cell(&mut self, x: u16, y: u16, cell: Cell) -> Result<(), DeltaError> {
+        let index = self.index(x, y).ok_or(DeltaError::OutOfBounds { x, y })?;
+        self.cells[index] = cell;
+
+        Ok(())
+    }
+
+    /// Moves the frame forward to `delta.tick`, the frame is left untouched if it fails
+    pub fn apply(&mut self, delta: &Delta) -> Result<(), DeltaError> {
+        if delta.base_tick != self.tick {
+            return Err(DeltaError::BaseTickMismatch {
+                expected: delta.base_tick,
+                found: self.tick,
+            });
+        }
+        if let Some(change) = delta
+            .changes
+            .iter()
+            .find(|c| self.index(c.x, c.y).is_none())
+        {
+            return Err(DeltaError::OutOfBounds {
+                x: change.x,
+                y: change.y,
+            });
+        }
+
+        for change in &delta.changes {
+            self.set_cell(change.x, change.y, change.cell)?;
+        }
+        self.tick = delta.tick;
+        self.score = delta.score;
+
+        Ok(())
+    }
+
+    /// Cells which differ between this frame and `next`, both have to be of the same size
+    pub fn diff(&self, next: &Frame) -> Delta {
+        let changes = self
+            .cells
+            .iter()
+            .zip(&next.cells)
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(i, (_, after))| CellChange {
+                x: (i % next.width as usize) as u16,
+                y: (i / next.width as usize) as u16,
+                cell: *after,
+            })
+            .collect();
+
+        Delta {
+            tick: next.tick,
+            base_tick: self.tick,
+            score: next.score,
+            changes,
+        }
+    }
+}
+
+/// Applies an update on top of the previous frame, which is `None` until the first keyframe arrives
+pub fn apply_update(previous: Option<Frame>, update: FrameUpdate) -> Result<Frame, DeltaError> {
+    match (previous, update) {
+        (_, FrameUpdate::Keyframe(frame)) => Ok(frame),
+        (Some(mut frame), FrameUpdate::Delta(delta)) => {
+            frame.apply(&delta)?;
+            Ok(frame)
+        }
+        (None, FrameUpdate::Delta(_)) => Err(DeltaError::MissingKeyframe),
+    }
+}
+
+impl From<&Frame> for BoardState {
+    fn from(frame: &Frame) -> Self {
+        let mut state = BoardState {
+            width: frame.width,
+            height: frame.height,
+            // Frames leave the edges out, they stay the same for the whole game
+            topology: Topology::default(),
+            score: frame.score,
+            head: None,
+            snake: vec![],
+            fruits: vec![],
+            walls: vec![],
+            portals: vec![],
+            level: None,
+        };
+        let mut portals: Vec<Vec<Point>> = vec![];
+
+        for (i, cell) in frame.cells.iter().enumerate() {
+            let point = Point::new(
+                (i / frame.width as usize) as u16,
+                (i % frame.width as usize) as u16,
+            );
+            match cell {
+                Cell::Head => state.head = Some(point),
+                Cell::Snake => state.snake.push(point),
+                Cell::Fruit => state.fruits.push(point),
+                Cell::Wall => state.walls.push(point),
+                Cell::Portal(pair) => {
+                    let pair = *pair as usize;
+                    if portals.len() <= pair {
+                        portals.resize(pair + 1, vec![]);
+                    }
+                    portals[pair].push(point);
+                }
+                Cell::Empty => {}
+            }
+        }
+        // Ends of a pair are listed in the order they appear, row by row
+        state.portals = portals
+            .into_iter()
+            .filter_map(|ends| match ends[..] {
+                [entrance, exit] => Some(Portal { entrance, exit }),
+                _ => None,
+            })
+            .collect();
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn frame() -> Frame {
+        let mut frame = Frame::empty(1, 3, 2);
+        frame.set_cell(0, 0, Cell::Head).unwrap();
+        frame.set_cell(1, 0, Cell::Snake).unwrap();
+        frame
+    }
+
+    #[test]
+    fn test_apply_delta() {
+        let mut frame = frame();
+        let delta = Delta {
+            tick: 2,
+            base_tick: 1,
+            score: 1,
+            changes: vec![
+                CellChange {
+                    x: 0,
+                    y: 1,
+                    cell: Cell::Head,
+                },
+                CellChange {
+                    x: 0,
+                    y: 0,
+                    cell: Cell::Snake,
+                },
+                CellChange {
+                    x: 1,
+                    y: 0,
+                    cell: Cell::Empty,
+                },
+            ],
+        };
+
+        frame.apply(&delta).unwrap();
+
+        assert_eq!(frame.tick, 2);
+        assert_eq!(frame.score, 1);
+        assert_eq!(
+            frame.cells,
+            vec![
+                Cell::Snake,
+                Cell::Empty,
+                Cell::Empty,
+                Cell::Head,
+                Cell::Empty,
+                Cell::Empty
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_delta_on_wrong_tick() {
+        let mut frame = frame();
+        let delta = Delta {
+            tick: 5,
+            base_tick: 4,
+            score: 0,
+            changes: vec![],
+        };
+
+        assert_eq!(
+            frame.apply(&delta),
+            Err(DeltaError::BaseTickMismatch {
+                expected: 4,
+                found: 1
+            })
+        );
+        assert_eq!(frame, self::frame());
+    }
+
+    #[test]
+    fn test_apply_delta_out_of_bounds_leaves_frame_untouched() {
+        let mut frame = frame();
+        let delta = Delta {
+            tick: 2,
+            base_tick: 1,
+            score: 0,
+            changes: vec![
+                CellChange {
+                    x: 2,
+                    y: 1,
+                    cell: Cell::Fruit,
+                },
+                CellChange {
+                    x: 3,
+                    y: 0,
+                    cell: Cell::Fruit,
+                },
+            ],
+        };
+
+        assert_eq!(
+            frame.apply(&delta),
+            Err(DeltaError::OutOfBounds { x: 3, y: 0 })
+        );
+        assert_eq!(frame, self::frame());
+    }
+
+    #[test]
+    fn test_diff_then_apply_gives_next_frame() {
+        let previous = frame();
+        let mut next = frame();
+        next.tick = 2;
+        next.set_cell(2, 1, Cell::Fruit).unwrap();
+        next.set_cell(0, 0, Cell::Empty).unwrap();
+
+        let delta = previous.diff(&next);
+        let applied = apply_update(Some(previous), FrameUpdate::Delta(delta.clone())).unwrap();
+
+        assert_eq!(delta.changes.len(), 2);
+        assert_eq!(applied, next);
+    }
+
+    #[test]
+    fn test_delta_before_keyframe() {
+        let update = FrameUpdate::Delta(Delta {
+            tick: 1,
+            base_tick: 0,
+            score: 0,
+            changes: vec![],
+        });
+
+        assert_eq!(apply_update(None, update), Err(DeltaError::MissingKeyframe));
+    }
+
+    #[test]
+    fn test_update_serialization_is_tagged() {
+        let update = FrameUpdate::Delta(Delta {
+            tick: 2,
+            base_tick: 1,
+            score: 0,
+            changes: vec![CellChange {
+                x: 1,
+                y: 2,
+                cell: Cell::Head,
+            }],
+        });
+
+        assert_eq!(
+            serde_json::to_string(&update).unwrap(),
+            r#"{"type":"delta","tick":2,"base_tick":1,"score":0,"changes":[{"x":1,"y":2,"cell":"head"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_board_state_from_frame() {
+        let mut frame = frame();
+        frame.set_cell(2, 1, Cell::Wall).unwrap();
+        frame.set_cell(2, 0, Cell::Portal(0)).unwrap();
+        frame.set_cell(1, 1, Cell::Portal(0)).unwrap();
+
+        let state = BoardState::from(&frame);
+
+        assert_eq!(state.head, Some(Point::new(0, 0)));
+        assert_eq!(state.snake, vec![Point::new(0, 1)]);
+        assert_eq!(state.walls, vec![Point::new(1, 2)]);
+        assert_eq!(
+            state.portals,
+            vec![Portal {
+                entrance: Point::new(0, 2),
+                exit: Point::new(1, 1)
+            }]
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use super::point::Direction;
+
+use serde::{Deserialize, Serialize};
+
+/// Which moves are safe to make next, for players who would rather not count cells themselves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Hint {
+    /// Tick of the board the hint is computed from
+    pub tick: u64,
+    /// Directions which do not run into the snake on the next tick, the reverse of the current
+    /// direction is never listed as the game ignores it
+    pub safe: Vec<Direction>,
+    /// Safe directions leading into an area too small to fit the whole snake
+    pub dead_ends: Vec<Direction>,
+    /// Moves along the shortest path to the nearest fruit, missing when no fruit can be reached
+    pub path_to_fruit: Option<Vec<Direction>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_hint_json() {
+        let hint = Hint {
+            tick: 3,
+            safe: vec![Direction::Up, Direction::Left],
+            dead_ends: vec![Direction::Left],
+            path_to_fruit: Some(vec![Direction::Up, Direction::Up]),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&hint).unwrap(),
+            r#"{"tick":3,"safe":["up","left"],"dead_ends":["left"],"path_to_fruit":["up","up"]}"#
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// What has to be reached to clear a stage of the campaign
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Goal {
+    /// Snake at least this long, counting the head
+    Length(u32),
+    /// Fruits eaten during the stage
+    Score(u32),
+}
+
+impl Goal {
+    pub fn is_reached(&self, length: u32, score: u32) -> bool {
+        match *self {
+            Self::Length(target) => length >= target,
+            Self::Score(target) => score >= target,
+        }
+    }
+}
+
+/// Stage of the campaign being played
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Level {
+    /// Counted from 1
+    pub number: u32,
+    /// Stages in the whole campaign
+    pub stages: u32,
+    pub name: String,
+    pub goal: Goal,
+    /// Games played on this stage so far, this one included
+    pub attempt: u32,
+    /// Times the whole campaign has been cleared since the server started
+    pub completed: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_level_json() {
+        let level = Level {
+            number: 2,
+            stages: 6,
+            name: "Rooms".to_string(),
+            goal: Goal::Length(12),
+            attempt: 1,
+            completed: 0,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&level).unwrap(),
+            r#"{"number":2,"stages":6,"name":"Rooms","goal":{"length":12},"attempt":1,"completed":0}"#
+        );
+    }
+
+    #[test]
+    fn test_goal_is_reached() {
+        assert!(Goal::Length(5).is_reached(5, 0));
+        assert!(!Goal::Length(5).is_reached(4, 10));
+        assert!(Goal::Score(3).is_reached(3, 3));
+    }
+}
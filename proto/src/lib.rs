@@ -0,0 +1,26 @@
+mod clock;
+mod frame;
+mod hint;
+mod level;
+mod plan;
+mod point;
+mod problem;
+mod state;
+mod topology;
+mod wire;
+
+pub use clock::{ServerClock, NEXT_TICK_IN_MS_HEADER, TICK_HEADER};
+pub use frame::{apply_update, Cell, CellChange, Delta, DeltaError, Frame, FrameUpdate};
+pub use hint::Hint;
+pub use level::{Goal, Level};
+pub use plan::{MovePlan, PlanReceipt, PlannedMove};
+pub use point::{Direction, DirectionError, Point};
+pub use problem::{error_code, Problem, PROBLEM_CONTENT_TYPE};
+pub use state::{BoardState, Portal};
+pub use topology::{Edge, Topology, TopologyError};
+pub use wire::{WireError, WireMessage, WIRE_CONTENT_TYPE, WIRE_VERSION};
+
+pub const BOARD_SIZE_Y: u16 = 20;
+pub const BOARD_SIZE_X: u16 = 40;
+/// Most pairs of portals a board can have, every pair gets a cell code of its own
+pub const MAX_PORTAL_PAIRS: usize = 8;
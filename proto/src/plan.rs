@@ -0,0 +1,76 @@
+use super::point::Direction;
+
+use serde::{Deserialize, Serialize};
+
+/// Move of a plan, made in `tick`, or in the tick after the previous move when it is missing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlannedMove {
+    pub direction: Direction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick: Option<u64>,
+}
+
+impl From<Direction> for PlannedMove {
+    fn from(direction: Direction) -> Self {
+        Self {
+            direction,
+            tick: None,
+        }
+    }
+}
+
+/// Moves submitted at once, replacing whatever is left of the client's previous plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MovePlan {
+    pub moves: Vec<PlannedMove>,
+}
+
+impl FromIterator<Direction> for MovePlan {
+    /// Plan of consecutive moves, starting with the next tick
+    fn from_iter<I: IntoIterator<Item = Direction>>(directions: I) -> Self {
+        Self {
+            moves: directions.into_iter().map(PlannedMove::from).collect(),
+        }
+    }
+}
+
+/// Accepted plan, along with the ticks its first and last moves are made in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlanReceipt {
+    /// Identifies the plan when cancelling it
+    pub id: u64,
+    pub first_tick: u64,
+    pub last_tick: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_plan_json() {
+        let plan: MovePlan = serde_json::from_str(
+            r#"{"moves":[{"direction":"left","tick":12},{"direction":"up"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            plan.moves,
+            vec![
+                PlannedMove {
+                    direction: Direction::Left,
+                    tick: Some(12)
+                },
+                PlannedMove::from(Direction::Up),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&[Direction::Down].into_iter().collect::<MovePlan>()).unwrap(),
+            r#"{"moves":[{"direction":"down"}]}"#
+        );
+    }
+}
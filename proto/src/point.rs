@@ -0,0 +1,150 @@
+use super::topology::Topology;
+use serde::{Deserialize, Serialize};
+use std::ops;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+            Self::Left => "left",
+            Self::Right => "right",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DirectionError {
+    #[error("There are 4 possible directions: ['left', 'right', 'up', 'down']. `{0}` does not match any of them")]
+    ConversionFromStringError(String),
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<String> for Direction {
+    type Error = DirectionError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            _ => Err(DirectionError::ConversionFromStringError(value)),
+        }
+    }
+}
+
+impl From<Direction> for i16 {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::Up => -1,
+            Direction::Down => 1,
+            Direction::Right => 1,
+            Direction::Left => -1,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Point {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl Point {
+    pub fn new(y: u16, x: u16) -> Self {
+        Self { y, x }
+    }
+
+    pub fn set_coords(&mut self, (y, x): (u16, u16)) {
+        self.x = x;
+        self.y = y;
+    }
+
+    pub fn get_coords(&self) -> (u16, u16) {
+        (self.y, self.x)
+    }
+}
+
+impl ops::AddAssign<Direction> for Point {
+    /// Moves on the classic board, `Topology::step` moves on the others
+    fn add_assign(&mut self, rhs: Direction) {
+        *self = Topology::TORUS
+            .step(*self, rhs)
+            .expect("torus has no walls");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Direction, Point};
+    use crate::{BOARD_SIZE_X, BOARD_SIZE_Y};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_point_add_assign_increase_y_in_bounds() {
+        let direction = Direction::Down;
+
+        let mut point = Point::new(6, 0);
+        point += direction;
+
+        assert_eq!(point.y, 7);
+        assert_eq!(point.x, 0);
+    }
+
+    #[test]
+    fn test_point_add_assign_increase_x_in_bounds() {
+        let direction = Direction::Left;
+
+        let mut point = Point::new(0, 5);
+        point += direction;
+
+        assert_eq!(point.y, 0);
+        assert_eq!(point.x, 4);
+    }
+
+    #[test]
+    fn test_point_add_assign_increase_out_of_lower_bound() {
+        let direction = Direction::Left;
+
+        let mut point = Point::new(0, 0);
+        point += direction;
+
+        assert_eq!(point.x, BOARD_SIZE_X - 1);
+    }
+
+    #[test]
+    fn test_point_add_assign_increase_out_of_upper_bound() {
+        let direction = Direction::Down;
+
+        let mut point = Point::new(BOARD_SIZE_Y - 1, 0);
+        point += direction;
+
+        assert_eq!(point.y, 0);
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+/// Media type of `Problem` bodies, as defined by RFC 7807
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Stable, machine-readable error codes, clients should match on these instead of messages
+pub mod error_code {
+    pub const INVALID_DIRECTION: &str = "invalid_direction";
+    pub const INVALID_COMMAND: &str = "invalid_command";
+    pub const REVERSE_MOVE_IGNORED: &str = "reverse_move_ignored";
+    pub const TICK_PASSED: &str = "tick_passed";
+    pub const RATE_LIMITED: &str = "rate_limited";
+    pub const QUEUE_FULL: &str = "queue_full";
+    pub const GAME_NOT_RUNNING: &str = "game_not_running";
+    pub const INVALID_QUERY: &str = "invalid_query";
+    pub const NOT_FOUND: &str = "not_found";
+    pub const UNSUPPORTED_VERSION: &str = "unsupported_version";
+    pub const INTERNAL_ERROR: &str = "internal_error";
+}
+
+/// Error response body of the HTTP API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Problem {
+    /// URI identifying the problem, `urn:snake:error:` followed by `code`
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Short summary of the status code
+    pub title: String,
+    pub status: u16,
+    /// Explanation of this occurrence of the problem
+    pub detail: String,
+    /// One of the `error_code`s
+    pub code: String,
+    /// How long to wait before retrying, for errors which go away on their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+impl Problem {
+    pub fn new(status: u16, title: &str, code: &str, detail: String) -> Self {
+        Self {
+            kind: format!("urn:snake:error:{code}"),
+            title: title.to_string(),
+            status,
+            detail,
+            code: code.to_string(),
+            retry_after_ms: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_problem_json() {
+        let mut problem = Problem::new(
+            429,
+            "Too Many Requests",
+            error_code::RATE_LIMITED,
+            "Slow down".to_string(),
+        );
+        problem.retry_after_ms = Some(100);
+
+        assert_eq!(
+            serde_json::to_string(&problem).unwrap(),
+            r#"{"type":"urn:snake:error:rate_limited","title":"Too Many Requests","status":429,"detail":"Slow down","code":"rate_limited","retry_after_ms":100}"#
+        );
+    }
+
+    #[test]
+    fn test_retry_hint_is_optional() {
+        let problem: Problem = serde_json::from_str(
+            r#"{"type":"urn:snake:error:not_found","title":"Not Found","status":404,"detail":"Gone","code":"not_found"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(problem.retry_after_ms, None);
+    }
+}
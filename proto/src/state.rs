@@ -0,0 +1,37 @@
+use super::level::Level;
+use super::point::Point;
+use super::topology::Topology;
+
+use serde::{Deserialize, Serialize};
+
+/// Pair of portals, a head moving onto either end comes out of the other one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Portal {
+    pub entrance: Point,
+    pub exit: Point,
+}
+
+/// Structured view of the board for clients which draw it themselves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BoardState {
+    pub width: u16,
+    pub height: u16,
+    /// How the snake crosses the edges of the board
+    #[serde(default)]
+    pub topology: Topology,
+    pub score: u32,
+    pub head: Option<Point>,
+    pub snake: Vec<Point>,
+    pub fruits: Vec<Point>,
+    /// Walls put up inside the board
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub walls: Vec<Point>,
+    /// Pairs of portals, in the order of their cell numbers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub portals: Vec<Portal>,
+    /// Stage being played in campaign mode, missing otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<Level>,
+}
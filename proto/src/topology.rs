@@ -0,0 +1,252 @@
+use super::point::{Direction, Point};
+use super::{BOARD_SIZE_X, BOARD_SIZE_Y};
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// What happens to the snake leaving the board through one of a pair of opposite edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    /// Comes back through the opposite edge
+    #[default]
+    Wrap,
+    /// Comes back through the opposite edge, mirrored along it
+    Mirror,
+    /// Runs into a wall, which ends the game
+    Wall,
+}
+
+impl Edge {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Wrap => "wrap",
+            Self::Mirror => "mirror",
+            Self::Wall => "wall",
+        }
+    }
+}
+
+/// Shape of the board, given by how each pair of its opposite edges behaves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Topology {
+    /// Left and right edges, mirroring flips the row the snake comes back in
+    pub horizontal: Edge,
+    /// Top and bottom edges, mirroring flips the column the snake comes back in
+    pub vertical: Edge,
+}
+
+impl Topology {
+    /// Every edge wraps around, the classic board
+    pub const TORUS: Self = Self::new(Edge::Wrap, Edge::Wrap);
+    /// Walls all around
+    pub const BOX: Self = Self::new(Edge::Wall, Edge::Wall);
+    /// Wraps around horizontally only
+    pub const CYLINDER: Self = Self::new(Edge::Wrap, Edge::Wall);
+    /// Mirrored horizontally, walls at the top and bottom
+    pub const MOBIUS: Self = Self::new(Edge::Mirror, Edge::Wall);
+    /// Mirrored horizontally, wraps around vertically
+    pub const KLEIN_BOTTLE: Self = Self::new(Edge::Mirror, Edge::Wrap);
+    /// Mirrored both ways
+    pub const PROJECTIVE_PLANE: Self = Self::new(Edge::Mirror, Edge::Mirror);
+
+    const NAMED: [(&'static str, Self); 6] = [
+        ("torus", Self::TORUS),
+        ("box", Self::BOX),
+        ("cylinder", Self::CYLINDER),
+        ("mobius", Self::MOBIUS),
+        ("klein", Self::KLEIN_BOTTLE),
+        ("projective", Self::PROJECTIVE_PLANE),
+    ];
+
+    pub const fn new(horizontal: Edge, vertical: Edge) -> Self {
+        Self {
+            horizontal,
+            vertical,
+        }
+    }
+
+    /// Edges crossed when leaving the board in `direction`
+    pub fn edge(&self, direction: Direction) -> Edge {
+        match direction {
+            Direction::Left | Direction::Right => self.horizontal,
+            Direction::Up | Direction::Down => self.vertical,
+        }
+    }
+
+    /// Cell next to `point` in `direction`, `None` when there is a wall in the way
+    pub fn step(&self, point: Point, direction: Direction) -> Option<Point> {
+        let mut next = point;
+        let crossed = match direction {
+            Direction::Up => next.y == 0,
+            Direction::Down => next.y == BOARD_SIZE_Y - 1,
+            Direction::Left => next.x == 0,
+            Direction::Right => next.x == BOARD_SIZE_X - 1,
+        };
+        if !crossed {
+            match direction {
+                Direction::Up => next.y -= 1,
+                Direction::Down => next.y += 1,
+                Direction::Left => next.x -= 1,
+                Direction::Right => next.x += 1,
+            }
+            return Some(next);
+        }
+
+        match (direction, self.edge(direction)) {
+            (_, Edge::Wall) => return None,
+            (Direction::Up, _) => next.y = BOARD_SIZE_Y - 1,
+            (Direction::Down, _) => next.y = 0,
+            (Direction::Left, _) => next.x = BOARD_SIZE_X - 1,
+            (Direction::Right, _) => next.x = 0,
+        }
+        if self.edge(direction) == Edge::Mirror {
+            match direction {
+                Direction::Left | Direction::Right => next.y = BOARD_SIZE_Y - 1 - next.y,
+                Direction::Up | Direction::Down => next.x = BOARD_SIZE_X - 1 - next.x,
+            }
+        }
+
+        Some(next)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TopologyError {
+    #[error("There are 6 possible topologies: ['torus', 'box', 'cylinder', 'mobius', 'klein', 'projective'], or edges can be given as `<left and right>,<top and bottom>`, each one of ['wrap', 'mirror', 'wall']. `{0}` does not match any of them")]
+    ConversionFromStringError(String),
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Self::NAMED.iter().find(|(_, topology)| topology == self) {
+            Some((name, _)) => f.write_str(name),
+            None => write!(f, "{},{}", self.horizontal.as_str(), self.vertical.as_str()),
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = TopologyError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let lowercase = value.trim().to_lowercase();
+        if let Some((_, topology)) = Self::NAMED.iter().find(|(name, _)| *name == lowercase) {
+            return Ok(*topology);
+        }
+
+        let edge = |name: &str| match name.trim() {
+            "wrap" => Some(Edge::Wrap),
+            "mirror" => Some(Edge::Mirror),
+            "wall" => Some(Edge::Wall),
+            _ => None,
+        };
+        lowercase
+            .split_once(',')
+            .and_then(|(horizontal, vertical)| Some(Self::new(edge(horizontal)?, edge(vertical)?)))
+            .ok_or_else(|| TopologyError::ConversionFromStringError(value.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const LAST_Y: u16 = BOARD_SIZE_Y - 1;
+    const LAST_X: u16 = BOARD_SIZE_X - 1;
+
+    #[test]
+    fn test_step_inside_board_is_same_everywhere() {
+        for (_, topology) in Topology::NAMED {
+            assert_eq!(
+                topology.step(Point::new(3, 4), Direction::Right),
+                Some(Point::new(3, 5))
+            );
+            assert_eq!(
+                topology.step(Point::new(3, 4), Direction::Up),
+                Some(Point::new(2, 4))
+            );
+        }
+    }
+
+    #[test]
+    fn test_torus_wraps_around() {
+        let topology = Topology::TORUS;
+
+        assert_eq!(
+            topology.step(Point::new(3, 0), Direction::Left),
+            Some(Point::new(3, LAST_X))
+        );
+        assert_eq!(
+            topology.step(Point::new(LAST_Y, 4), Direction::Down),
+            Some(Point::new(0, 4))
+        );
+    }
+
+    #[test]
+    fn test_box_has_walls() {
+        let topology = Topology::BOX;
+
+        assert_eq!(topology.step(Point::new(3, 0), Direction::Left), None);
+        assert_eq!(topology.step(Point::new(0, 4), Direction::Up), None);
+    }
+
+    #[test]
+    fn test_cylinder_wraps_horizontally_only() {
+        let topology = Topology::CYLINDER;
+
+        assert_eq!(
+            topology.step(Point::new(3, LAST_X), Direction::Right),
+            Some(Point::new(3, 0))
+        );
+        assert_eq!(topology.step(Point::new(LAST_Y, 4), Direction::Down), None);
+    }
+
+    #[test]
+    fn test_klein_bottle_mirrors_rows() {
+        let topology = Topology::KLEIN_BOTTLE;
+
+        assert_eq!(
+            topology.step(Point::new(3, LAST_X), Direction::Right),
+            Some(Point::new(LAST_Y - 3, 0))
+        );
+        assert_eq!(
+            topology.step(Point::new(0, 4), Direction::Up),
+            Some(Point::new(LAST_Y, 4))
+        );
+    }
+
+    #[test]
+    fn test_projective_plane_mirrors_columns_too() {
+        let topology = Topology::PROJECTIVE_PLANE;
+
+        assert_eq!(
+            topology.step(Point::new(0, 4), Direction::Up),
+            Some(Point::new(LAST_Y, LAST_X - 4))
+        );
+    }
+
+    #[test]
+    fn test_parse_topology() {
+        assert_eq!("Klein".parse::<Topology>().unwrap(), Topology::KLEIN_BOTTLE);
+        assert_eq!(
+            "wall, wrap".parse::<Topology>().unwrap(),
+            Topology::new(Edge::Wall, Edge::Wrap)
+        );
+        assert!("sphere".parse::<Topology>().is_err());
+        assert!("wrap,bounce".parse::<Topology>().is_err());
+    }
+
+    #[test]
+    fn test_topology_display_round_trips() {
+        for topology in [Topology::MOBIUS, Topology::new(Edge::Wall, Edge::Mirror)] {
+            assert_eq!(topology.to_string().parse::<Topology>().unwrap(), topology);
+        }
+        assert_eq!(Topology::TORUS.to_string(), "torus");
+    }
+}
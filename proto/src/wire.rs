@@ -0,0 +1,638 @@
+//! Compact binary encoding of the protocol messages
+//!
+//! Every message starts with the format version and a kind byte, followed by its payload.
+//! Integers are big endian, points are `x` followed by `y` and keyframe cells are packed
+//! two to a byte, four bits each. Strings are UTF-8 behind their length.
+
+use super::frame::{Cell, CellChange, Delta, Frame, FrameUpdate};
+use super::level::{Goal, Level};
+use super::point::{Direction, Point};
+use super::state::{BoardState, Portal};
+use super::topology::{Edge, Topology};
+use super::MAX_PORTAL_PAIRS;
+
+use thiserror::Error;
+
+/// Version written into, and expected from, every message
+pub const WIRE_VERSION: u8 = 3;
+
+/// Media type of binary messages, used for content negotiation
+pub const WIRE_CONTENT_TYPE: &str = "application/vnd.snake.v1";
+
+const KIND_STATE: u8 = 1;
+const KIND_KEYFRAME: u8 = 2;
+const KIND_DELTA: u8 = 3;
+const KIND_COMMAND: u8 = 4;
+const KIND_SHUTDOWN: u8 = 5;
+
+/// Bits a keyframe cell is packed in
+const CELL_BITS: usize = 4;
+const CELLS_PER_BYTE: usize = 8 / CELL_BITS;
+const CELL_MASK: u8 = (1 << CELL_BITS) - 1;
+/// Code of the first pair of portals, the following pairs take the codes after it
+const FIRST_PORTAL_CODE: u8 = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireMessage {
+    State(BoardState),
+    Update(FrameUpdate),
+    Command(Direction),
+    /// Last message of a stream, the server is going away after `tick`
+    Shutdown {
+        tick: u64,
+    },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WireError {
+    #[error("Wire format version {0} is not supported, expected {WIRE_VERSION}")]
+    UnsupportedVersion(u8),
+
+    #[error("Unknown message kind {0}")]
+    UnknownKind(u8),
+
+    #[error("Message ended unexpectedly")]
+    UnexpectedEnd,
+
+    #[error("Message has {0} unexpected trailing bytes")]
+    TrailingBytes(usize),
+
+    #[error("Unknown cell {0}")]
+    UnknownCell(u8),
+
+    #[error("Unknown direction {0}")]
+    UnknownDirection(u8),
+
+    #[error("Unknown edge {0}")]
+    UnknownEdge(u8),
+
+    #[error("Unknown goal {0}")]
+    UnknownGoal(u8),
+
+    #[error("Text is not valid UTF-8")]
+    InvalidText,
+}
+
+impl WireMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![WIRE_VERSION];
+
+        match self {
+            Self::State(state) => {
+                out.push(KIND_STATE);
+                put_u16(&mut out, state.width);
+                put_u16(&mut out, state.height);
+                out.push(edge_code(state.topology.horizontal));
+                out.push(edge_code(state.topology.vertical));
+                put_u32(&mut out, state.score);
+                match &state.head {
+                    Some(head) => {
+                        out.push(1);
+                        put_point(&mut out, head);
+                    }
+                    None => out.push(0),
+                }
+                put_points(&mut out, &state.snake);
+                put_points(&mut out, &state.fruits);
+                put_points(&mut out, &state.walls);
+                put_u32(&mut out, state.portals.len() as u32);
+                for portal in &state.portals {
+                    put_point(&mut out, &portal.entrance);
+                    put_point(&mut out, &portal.exit);
+                }
+                match &state.level {
+                    Some(level) => {
+                        out.push(1);
+                        put_level(&mut out, level);
+                    }
+                    None => out.push(0),
+                }
+            }
+            Self::Update(FrameUpdate::Keyframe(frame)) => {
+                out.push(KIND_KEYFRAME);
+                put_u64(&mut out, frame.tick);
+                put_u16(&mut out, frame.width);
+                put_u16(&mut out, frame.height);
+                put_u32(&mut out, frame.score);
+                for cells in frame.cells.chunks(CELLS_PER_BYTE) {
+                    let packed = cells.iter().enumerate().fold(0, |byte, (i, cell)| {
+                        byte | cell_code(*cell) << (i * CELL_BITS)
+                    });
+                    out.push(packed);
+                }
+            }
+            Self::Update(FrameUpdate::Delta(delta)) => {
+                out.push(KIND_DELTA);
+                put_u64(&mut out, delta.tick);
+                put_u64(&mut out, delta.base_tick);
+                put_u32(&mut out, delta.score);
+                put_u32(&mut out, delta.changes.len() as u32);
+                for change in &delta.changes {
+                    put_u16(&mut out, change.x);
+                    put_u16(&mut out, change.y);
+                    out.push(cell_code(change.cell));
+                }
+            }
+            Self::Command(direction) => {
+                out.push(KIND_COMMAND);
+                out.push(direction_code(*direction));
+            }
+            Self::Shutdown { tick } => {
+                out.push(KIND_SHUTDOWN);
+                put_u64(&mut out, *tick);
+            }
+        }
+
+        out
+    }
+
+    /// Decodes exactly one message, which has to span the whole buffer
+    pub fn decode(buf: &[u8]) -> Result<Self, WireError> {
+        let mut reader = Reader { buf };
+
+        let version = reader.u8()?;
+        if version != WIRE_VERSION {
+            return Err(WireError::UnsupportedVersion(version));
+        }
+
+        let message = match reader.u8()? {
+            KIND_STATE => {
+                let width = reader.u16()?;
+                let height = reader.u16()?;
+                let topology =
+                    Topology::new(edge_from_code(reader.u8()?)?, edge_from_code(reader.u8()?)?);
+                let score = reader.u32()?;
+                let head = match reader.u8()? {
+                    0 => None,
+                    _ => Some(reader.point()?),
+                };
+                Self::State(BoardState {
+                    width,
+                    height,
+                    topology,
+                    score,
+                    head,
+                    snake: reader.points()?,
+                    fruits: reader.points()?,
+                    walls: reader.points()?,
+                    portals: reader.portals()?,
+                    level: match reader.u8()? {
+                        0 => None,
+                        _ => Some(reader.level()?),
+                    },
+                })
+            }
+            KIND_KEYFRAME => {
+                let tick = reader.u64()?;
+                let width = reader.u16()?;
+                let height = reader.u16()?;
+                let score = reader.u32()?;
+                let len = width as usize * height as usize;
+                let packed = reader.bytes(len.div_ceil(CELLS_PER_BYTE))?;
+                let cells = (0..len)
+                    .map(|i| {
+                        let shift = (i % CELLS_PER_BYTE) * CELL_BITS;
+                        cell_from_code((packed[i / CELLS_PER_BYTE] >> shift) & CELL_MASK)
+                    })
+                    .collect::<Result<_, _>>()?;
+                Self::Update(FrameUpdate::Keyframe(Frame {
+                    tick,
+                    width,
+                    height,
+                    score,
+                    cells,
+                }))
+            }
+            KIND_DELTA => {
+                let tick = reader.u64()?;
+                let base_tick = reader.u64()?;
+                let score = reader.u32()?;
+                let len = reader.u32()?;
+                let changes = (0..len)
+                    .map(|_| {
+                        Ok(CellChange {
+                            x: reader.u16()?,
+                            y: reader.u16()?,
+                            cell: cell_from_code(reader.u8()?)?,
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                Self::Update(FrameUpdate::Delta(Delta {
+                    tick,
+                    base_tick,
+                    score,
+                    changes,
+                }))
+            }
+            KIND_COMMAND => Self::Command(direction_from_code(reader.u8()?)?),
+            KIND_SHUTDOWN => Self::Shutdown {
+                tick: reader.u64()?,
+            },
+            kind => return Err(WireError::UnknownKind(kind)),
+        };
+
+        match reader.buf.len() {
+            0 => Ok(message),
+            trailing => Err(WireError::TrailingBytes(trailing)),
+        }
+    }
+
+    /// Encodes the message behind its length, so several of them can share a stream
+    pub fn encode_length_prefixed(&self) -> Vec<u8> {
+        let message = self.encode();
+        let mut out = Vec::with_capacity(message.len() + 4);
+        put_u32(&mut out, message.len() as u32);
+        out.extend(message);
+
+        out
+    }
+
+    /// Decodes the first length prefixed message from a stream, returning it along with the
+    /// number of bytes it took, or `None` when the buffer does not hold a whole message yet
+    pub fn decode_length_prefixed(buf: &[u8]) -> Result<Option<(Self, usize)>, WireError> {
+        let mut reader = Reader { buf };
+        let Ok(len) = reader.u32() else {
+            return Ok(None);
+        };
+        let Ok(message) = reader.bytes(len as usize) else {
+            return Ok(None);
+        };
+
+        Ok(Some((Self::decode(message)?, len as usize + 4)))
+    }
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_point(out: &mut Vec<u8>, point: &Point) {
+    put_u16(out, point.x);
+    put_u16(out, point.y);
+}
+
+fn put_points(out: &mut Vec<u8>, points: &[Point]) {
+    put_u32(out, points.len() as u32);
+    points.iter().for_each(|point| put_point(out, point));
+}
+
+fn cell_code(cell: Cell) -> u8 {
+    match cell {
+        Cell::Empty => 0,
+        Cell::Snake => 1,
+        Cell::Head => 2,
+        Cell::Fruit => 3,
+        Cell::Wall => 4,
+        Cell::Portal(pair) => FIRST_PORTAL_CODE + pair % MAX_PORTAL_PAIRS as u8,
+    }
+}
+
+fn cell_from_code(code: u8) -> Result<Cell, WireError> {
+    match code {
+        0 => Ok(Cell::Empty),
+        1 => Ok(Cell::Snake),
+        2 => Ok(Cell::Head),
+        3 => Ok(Cell::Fruit),
+        4 => Ok(Cell::Wall),
+        FIRST_PORTAL_CODE.. if code < FIRST_PORTAL_CODE + MAX_PORTAL_PAIRS as u8 => {
+            Ok(Cell::Portal(code - FIRST_PORTAL_CODE))
+        }
+        _ => Err(WireError::UnknownCell(code)),
+    }
+}
+
+fn direction_code(direction: Direction) -> u8 {
+    match direction {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    }
+}
+
+fn direction_from_code(code: u8) -> Result<Direction, WireError> {
+    match code {
+        0 => Ok(Direction::Up),
+        1 => Ok(Direction::Down),
+        2 => Ok(Direction::Left),
+        3 => Ok(Direction::Right),
+        _ => Err(WireError::UnknownDirection(code)),
+    }
+}
+
+fn put_level(out: &mut Vec<u8>, level: &Level) {
+    put_u32(out, level.number);
+    put_u32(out, level.stages);
+    put_u32(out, level.name.len() as u32);
+    out.extend_from_slice(level.name.as_bytes());
+    let (goal, target) = match level.goal {
+        Goal::Length(target) => (0, target),
+        Goal::Score(target) => (1, target),
+    };
+    out.push(goal);
+    put_u32(out, target);
+    put_u32(out, level.attempt);
+    put_u32(out, level.completed);
+}
+
+fn edge_code(edge: Edge) -> u8 {
+    match edge {
+        Edge::Wrap => 0,
+        Edge::Mirror => 1,
+        Edge::Wall => 2,
+    }
+}
+
+fn edge_from_code(code: u8) -> Result<Edge, WireError> {
+    match code {
+        0 => Ok(Edge::Wrap),
+        1 => Ok(Edge::Mirror),
+        2 => Ok(Edge::Wall),
+        _ => Err(WireError::UnknownEdge(code)),
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], WireError> {
+        if self.buf.len() < len {
+            return Err(WireError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], WireError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, WireError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, WireError> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, WireError> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, WireError> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    fn point(&mut self) -> Result<Point, WireError> {
+        let x = self.u16()?;
+        let y = self.u16()?;
+
+        Ok(Point { x, y })
+    }
+
+    fn points(&mut self) -> Result<Vec<Point>, WireError> {
+        let len = self.u32()?;
+        (0..len).map(|_| self.point()).collect()
+    }
+
+    fn text(&mut self) -> Result<String, WireError> {
+        let len = self.u32()?;
+        let bytes = self.bytes(len as usize)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| WireError::InvalidText)
+    }
+
+    fn level(&mut self) -> Result<Level, WireError> {
+        let number = self.u32()?;
+        let stages = self.u32()?;
+        let name = self.text()?;
+        let goal = match self.u8()? {
+            0 => Goal::Length(self.u32()?),
+            1 => Goal::Score(self.u32()?),
+            code => return Err(WireError::UnknownGoal(code)),
+        };
+
+        Ok(Level {
+            number,
+            stages,
+            name,
+            goal,
+            attempt: self.u32()?,
+            completed: self.u32()?,
+        })
+    }
+
+    fn portals(&mut self) -> Result<Vec<Portal>, WireError> {
+        let len = self.u32()?;
+        (0..len)
+            .map(|_| {
+                Ok(Portal {
+                    entrance: self.point()?,
+                    exit: self.point()?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn state_json() -> &'static str {
+        r#"{"width":40,"height":20,"topology":{"horizontal":"mirror","vertical":"wall"},"score":3,"head":{"x":4,"y":2},"snake":[{"x":4,"y":3},{"x":4,"y":4}],"fruits":[{"x":0,"y":19}],"walls":[{"x":7,"y":7},{"x":7,"y":8}],"portals":[{"entrance":{"x":1,"y":1},"exit":{"x":30,"y":15}}],"level":{"number":2,"stages":6,"name":"Orchard","goal":{"length":12},"attempt":3,"completed":0}}"#
+    }
+
+    fn keyframe_json() -> &'static str {
+        r#"{"type":"keyframe","tick":12,"width":3,"height":3,"score":1,"cells":["head","snake",{"portal":7},"wall","fruit","empty","snake","wall",{"portal":7}]}"#
+    }
+
+    fn delta_json() -> &'static str {
+        r#"{"type":"delta","tick":13,"base_tick":12,"score":2,"changes":[{"x":1,"y":1,"cell":"head"},{"x":0,"y":0,"cell":{"portal":2}}]}"#
+    }
+
+    #[test]
+    fn test_state_round_trips_like_json() {
+        let state: BoardState = serde_json::from_str(state_json()).unwrap();
+        let message = WireMessage::State(state.clone());
+
+        let decoded = WireMessage::decode(&message.encode()).unwrap();
+
+        assert_eq!(decoded, WireMessage::State(state));
+    }
+
+    #[test]
+    fn test_state_without_level_round_trips() {
+        let mut state: BoardState = serde_json::from_str(state_json()).unwrap();
+        state.level = None;
+        let message = WireMessage::State(state);
+
+        let encoded = message.encode();
+
+        assert_eq!(encoded.last(), Some(&0));
+        assert_eq!(WireMessage::decode(&encoded), Ok(message));
+    }
+
+    #[test]
+    fn test_updates_round_trip_like_json() {
+        for json in [keyframe_json(), delta_json()] {
+            let update: FrameUpdate = serde_json::from_str(json).unwrap();
+            let message = WireMessage::Update(update);
+
+            let decoded = WireMessage::decode(&message.encode()).unwrap();
+
+            assert_eq!(decoded, message);
+        }
+    }
+
+    #[test]
+    fn test_commands_round_trip_like_json() {
+        for json in [r#""up""#, r#""down""#, r#""left""#, r#""right""#] {
+            let direction: Direction = serde_json::from_str(json).unwrap();
+
+            let decoded = WireMessage::decode(&WireMessage::Command(direction).encode()).unwrap();
+
+            assert_eq!(decoded, WireMessage::Command(direction));
+        }
+    }
+
+    #[test]
+    fn test_keyframe_is_smaller_than_json() {
+        let frame = Frame::empty(1, 40, 20);
+        let json = serde_json::to_vec(&FrameUpdate::Keyframe(frame.clone())).unwrap();
+
+        let encoded = WireMessage::Update(FrameUpdate::Keyframe(frame)).encode();
+
+        assert_eq!(encoded.len(), 2 + 8 + 2 + 2 + 4 + 400);
+        assert!(encoded.len() * 10 < json.len());
+    }
+
+    #[test]
+    fn test_walls_round_trip() {
+        let mut frame = Frame::empty(3, 5, 1);
+        frame.cells = vec![Cell::Wall, Cell::Head, Cell::Wall, Cell::Wall, Cell::Fruit];
+        let message = WireMessage::Update(FrameUpdate::Keyframe(frame.clone()));
+
+        let encoded = message.encode();
+        let decoded = WireMessage::decode(&encoded).unwrap();
+
+        assert_eq!(&encoded[encoded.len() - 3..], &[0x24, 0x44, 0x03]);
+        assert_eq!(decoded, message);
+        assert_eq!(BoardState::from(&frame).walls.len(), 3);
+    }
+
+    #[test]
+    fn test_portal_cells_are_numbered_by_pair() {
+        let mut frame = Frame::empty(3, 2, 1);
+        frame.cells = vec![Cell::Portal(0), Cell::Portal(5)];
+
+        let encoded = WireMessage::Update(FrameUpdate::Keyframe(frame)).encode();
+
+        assert_eq!(encoded.last(), Some(&0xd8));
+        assert_eq!(cell_from_code(5), Err(WireError::UnknownCell(5)));
+        assert_eq!(cell_from_code(16), Err(WireError::UnknownCell(16)));
+    }
+
+    #[test]
+    fn test_command_layout() {
+        assert_eq!(
+            WireMessage::Command(Direction::Left).encode(),
+            vec![WIRE_VERSION, KIND_COMMAND, 2]
+        );
+    }
+
+    #[test]
+    fn test_shutdown_round_trips() {
+        let message = WireMessage::Shutdown { tick: 258 };
+        let encoded = message.encode();
+
+        assert_eq!(
+            encoded,
+            vec![WIRE_VERSION, KIND_SHUTDOWN, 0, 0, 0, 0, 0, 0, 1, 2]
+        );
+        assert_eq!(WireMessage::decode(&encoded), Ok(message));
+    }
+
+    #[test]
+    fn test_rejects_other_versions() {
+        assert_eq!(
+            WireMessage::decode(&[2, KIND_COMMAND, 0]),
+            Err(WireError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_messages() {
+        assert_eq!(
+            WireMessage::decode(&[WIRE_VERSION, 9]),
+            Err(WireError::UnknownKind(9))
+        );
+        assert_eq!(
+            WireMessage::decode(&[WIRE_VERSION, KIND_COMMAND]),
+            Err(WireError::UnexpectedEnd)
+        );
+        assert_eq!(
+            WireMessage::decode(&[WIRE_VERSION, KIND_COMMAND, 7]),
+            Err(WireError::UnknownDirection(7))
+        );
+        assert_eq!(
+            WireMessage::decode(&[WIRE_VERSION, KIND_COMMAND, 0, 0]),
+            Err(WireError::TrailingBytes(1))
+        );
+        assert_eq!(
+            WireMessage::decode(&[WIRE_VERSION, KIND_STATE, 0, 3, 0, 2, 0, 7]),
+            Err(WireError::UnknownEdge(7))
+        );
+        assert_eq!(
+            Reader {
+                buf: &[0, 0, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0, 9]
+            }
+            .level(),
+            Err(WireError::UnknownGoal(9))
+        );
+        assert_eq!(
+            Reader {
+                buf: &[0, 0, 0, 1, 0xff]
+            }
+            .text(),
+            Err(WireError::InvalidText)
+        );
+    }
+
+    #[test]
+    fn test_length_prefixed_stream() {
+        let first = WireMessage::Command(Direction::Up);
+        let second: FrameUpdate = serde_json::from_str(delta_json()).unwrap();
+        let second = WireMessage::Update(second);
+        let mut stream = first.encode_length_prefixed();
+        stream.extend(second.encode_length_prefixed());
+
+        let (decoded, used) = WireMessage::decode_length_prefixed(&stream)
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded, first);
+        let (decoded, rest) = WireMessage::decode_length_prefixed(&stream[used..])
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded, second);
+        assert_eq!(used + rest, stream.len());
+
+        assert_eq!(
+            WireMessage::decode_length_prefixed(&stream[..used - 1]),
+            Ok(None)
+        );
+    }
+}
@@ -1,12 +1,36 @@
+pub mod analysis;
+mod arena;
 mod board;
+mod campaign;
 mod commands;
 mod consts;
 mod fruit;
+mod heartbeat;
+mod hint;
+pub mod image;
+mod layout;
+mod plan;
 mod point;
+mod recording;
+mod render;
 mod runner;
+mod shutdown;
 mod snake;
+mod snapshot;
+mod state;
 
-pub use board::Board;
-pub use commands::movement;
-pub use point::Direction;
-pub use runner::new_game;
+pub use arena::{Arena, ArenaError, ArenaOptions};
+pub use board::{Board, CellSymbol};
+pub use campaign::{Campaign, GameMode, Goal, Level, Stage, STAGES};
+pub use commands::{movement, MoveCommandIssuer};
+pub use consts::MAX_PORTAL_PAIRS;
+pub use heartbeat::{Heartbeat, Liveness, Phase};
+pub use hint::Hint;
+pub use layout::{Layout, LayoutError};
+pub use point::{Direction, Edge, Point, Topology};
+pub use recording::{GameRecorder, RecordedEvent, Recording};
+pub use render::{RenderStyle, Renderer};
+pub use runner::{new_game, spawn_game, GameEnd, GameHandles};
+pub use shutdown::{shutdown_signal, SHUTDOWN_DEADLINE};
+pub use snapshot::{Snapshot, Snapshots, TickTiming};
+pub use state::{BoardState, Cell, CellChange, Delta, Frame, FrameUpdate};
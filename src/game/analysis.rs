@@ -0,0 +1,778 @@
+//! Searches over the board, following the same topology and portals the snake moves by
+
+use super::arena::Arena;
+use super::consts::*;
+use super::point::{Direction, Edge, Point};
+
+use lazy_static::lazy_static;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+pub const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+const CELLS: usize = BOARD_SIZE_X as usize * BOARD_SIZE_Y as usize;
+
+lazy_static! {
+    /// Arena of the classic board, which default grids are searched in
+    static ref CLASSIC: Arena = Arena::default();
+}
+
+fn index_of(point: &Point) -> usize {
+    point.y as usize * BOARD_SIZE_X as usize + point.x as usize
+}
+
+fn point_at(index: usize) -> Point {
+    Point::new(
+        (index / BOARD_SIZE_X as usize) as u16,
+        (index % BOARD_SIZE_X as usize) as u16,
+    )
+}
+
+/// Board as the searches see it, its arena along with the cells which cannot be entered,
+/// such as the snake's body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<'a> {
+    arena: &'a Arena,
+    blocked: Vec<bool>,
+}
+
+impl<'a> Grid<'a> {
+    pub fn new(arena: &'a Arena) -> Self {
+        Self {
+            arena,
+            blocked: vec![false; CELLS],
+        }
+    }
+
+    pub fn arena(&self) -> &'a Arena {
+        self.arena
+    }
+
+    pub fn is_blocked(&self, point: &Point) -> bool {
+        self.blocked[index_of(point)]
+    }
+
+    pub fn block(&mut self, point: &Point) {
+        self.blocked[index_of(point)] = true;
+    }
+
+    pub fn unblock(&mut self, point: &Point) {
+        self.blocked[index_of(point)] = false;
+    }
+
+    /// Cell a move from `point` in `direction` leads to, whether it is blocked or not, `None`
+    /// past a wall. Portal cells are never returned, as moving onto one leads past its partner
+    pub fn neighbour(&self, point: Point, direction: Direction) -> Option<Point> {
+        self.arena.step(point, direction)
+    }
+
+    /// Cells next to `point` along with the direction leading to them
+    pub fn neighbours(&self, point: Point) -> impl Iterator<Item = (Direction, Point)> + '_ {
+        DIRECTIONS.into_iter().filter_map(move |direction| {
+            self.neighbour(point, direction)
+                .map(|next| (direction, next))
+        })
+    }
+
+    /// Least number of moves between two cells if nothing was blocked, which never overestimates
+    pub fn distance(&self, from: &Point, to: &Point) -> u16 {
+        let direct = self.topology_distance(from, to);
+        // Going through any number of portals takes at least the way to the nearest one, and
+        // from the nearest one on, less the move saved by coming out next to it
+        let portals = || self.arena.portals().iter().flat_map(|(a, b)| [*a, *b]);
+        let to_portal = portals()
+            .map(|portal| self.topology_distance(from, &portal))
+            .min();
+        let from_portal = portals()
+            .map(|portal| self.topology_distance(&portal, to))
+            .min();
+        match (to_portal, from_portal) {
+            (Some(to_portal), Some(from_portal)) => {
+                direct.min((to_portal + from_portal).saturating_sub(1))
+            }
+            _ => direct,
+        }
+    }
+
+    /// Least number of moves between two cells on an empty board without portals
+    fn topology_distance(&self, from: &Point, to: &Point) -> u16 {
+        let topology = self.arena.topology();
+        let mut shortest = u16::MAX;
+        for cross_horizontal in [false, true] {
+            for cross_vertical in [false, true] {
+                let crossed = |crossing: bool, edge: Edge| crossing && edge != Edge::Wall;
+                if cross_horizontal != crossed(cross_horizontal, topology.horizontal)
+                    || cross_vertical != crossed(cross_vertical, topology.vertical)
+                {
+                    continue;
+                }
+
+                // Crossing a mirrored edge once lands on the mirror image of the target
+                let mut target = *to;
+                if cross_horizontal && topology.horizontal == Edge::Mirror {
+                    target.y = BOARD_SIZE_Y - 1 - target.y;
+                }
+                if cross_vertical && topology.vertical == Edge::Mirror {
+                    target.x = BOARD_SIZE_X - 1 - target.x;
+                }
+                let along = |a: u16, b: u16, crossing: bool, bound: u16| match crossing {
+                    false => a.abs_diff(b),
+                    true => bound - a.abs_diff(b),
+                };
+
+                shortest = shortest.min(
+                    along(from.x, target.x, cross_horizontal, BOARD_SIZE_X)
+                        + along(from.y, target.y, cross_vertical, BOARD_SIZE_Y),
+                );
+            }
+        }
+
+        shortest
+    }
+}
+
+impl Default for Grid<'static> {
+    fn default() -> Self {
+        Self::new(&CLASSIC)
+    }
+}
+
+impl<'p> Extend<&'p Point> for Grid<'_> {
+    fn extend<I: IntoIterator<Item = &'p Point>>(&mut self, points: I) {
+        for point in points {
+            self.block(point);
+        }
+    }
+}
+
+/// Grid of the classic board with `points` blocked
+impl<'p> FromIterator<&'p Point> for Grid<'static> {
+    fn from_iter<I: IntoIterator<Item = &'p Point>>(points: I) -> Self {
+        let mut grid = Self::default();
+        grid.extend(points);
+
+        grid
+    }
+}
+
+/// Follows the moves recorded during a search back from `end`
+fn path_to(end: Point, came_from: &[Option<(Point, Direction)>]) -> Vec<Direction> {
+    let mut path = vec![];
+    let mut current = end;
+    while let Some((previous, moved)) = came_from[index_of(&current)] {
+        path.push(moved);
+        current = previous;
+    }
+    path.reverse();
+
+    path
+}
+
+/// Shortest path from `start` to the closest cell matching `is_goal`, `None` when none can be
+/// reached. `start` itself may be blocked, which is how the head usually is
+pub fn bfs(start: Point, grid: &Grid, is_goal: impl Fn(&Point) -> bool) -> Option<Vec<Direction>> {
+    let mut came_from = vec![None; CELLS];
+    let mut visited = grid.blocked.clone();
+    visited[index_of(&start)] = true;
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(point) = queue.pop_front() {
+        if is_goal(&point) {
+            return Some(path_to(point, &came_from));
+        }
+
+        for (moved, next) in grid.neighbours(point) {
+            if !visited[index_of(&next)] {
+                visited[index_of(&next)] = true;
+                came_from[index_of(&next)] = Some((point, moved));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Shortest path from `start` to `goal`, guided by `Grid::distance`, so it visits fewer cells
+/// than `bfs` when there is a single goal
+pub fn a_star(start: Point, goal: Point, grid: &Grid) -> Option<Vec<Direction>> {
+    let mut came_from = vec![None; CELLS];
+    let mut cost = vec![u16::MAX; CELLS];
+    cost[index_of(&start)] = 0;
+    // Ties go to the cell found last, which keeps following the current path
+    let mut open = BinaryHeap::from([(Reverse(grid.distance(&start, &goal)), 0, index_of(&start))]);
+    let mut order = 0_usize;
+
+    while let Some((_, _, index)) = open.pop() {
+        let point = point_at(index);
+        if point == goal {
+            return Some(path_to(point, &came_from));
+        }
+
+        let next_cost = cost[index] + 1;
+        for (moved, next) in grid.neighbours(point) {
+            let next_index = index_of(&next);
+            if grid.is_blocked(&next) || next_cost >= cost[next_index] {
+                continue;
+            }
+            cost[next_index] = next_cost;
+            came_from[next_index] = Some((point, moved));
+            order += 1;
+            open.push((
+                Reverse(next_cost + grid.distance(&next, &goal)),
+                order,
+                next_index,
+            ));
+        }
+    }
+
+    None
+}
+
+/// Number of moves from `start` to every cell, `None` for cells which cannot be reached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceMap(Vec<Option<u16>>);
+
+impl DistanceMap {
+    pub fn new(start: Point, grid: &Grid) -> Self {
+        let mut distances = vec![None; CELLS];
+        distances[index_of(&start)] = Some(0);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(point) = queue.pop_front() {
+            let next_distance = distances[index_of(&point)].map(|d| d + 1);
+            for (_, next) in grid.neighbours(point) {
+                let next_index = index_of(&next);
+                if !grid.is_blocked(&next) && distances[next_index].is_none() {
+                    distances[next_index] = next_distance;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        Self(distances)
+    }
+
+    pub fn get(&self, point: &Point) -> Option<u16> {
+        self.0[index_of(point)]
+    }
+
+    /// Reachable cells along with their distance, row by row
+    pub fn reachable(&self) -> impl Iterator<Item = (Point, u16)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, distance)| distance.map(|d| (point_at(index), d)))
+    }
+}
+
+/// Number of free cells reachable from `start`, not counting `start` unless it can be returned to
+pub fn region_size(start: Point, grid: &Grid) -> usize {
+    let mut visited = grid.blocked.clone();
+    let mut queue = VecDeque::from([start]);
+    let mut size = 0;
+
+    while let Some(point) = queue.pop_front() {
+        for (_, next) in grid.neighbours(point) {
+            if !visited[index_of(&next)] {
+                visited[index_of(&next)] = true;
+                size += 1;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    size
+}
+
+/// Whether the head of `body` (head first) can catch up with the tail, taking into account that
+/// every segment moves out of the way once the tail has passed it. A snake which can keep
+/// following its tail cannot get trapped, as long as it does not grow
+pub fn can_reach_tail(body: &[Point], grid: &Grid) -> bool {
+    let (Some(&head), Some(&tail)) = (body.first(), body.last()) else {
+        return false;
+    };
+    // Moves it takes for each cell of the body to become free
+    let mut free_after = vec![0_usize; CELLS];
+    for (segment, point) in body.iter().enumerate() {
+        free_after[index_of(point)] = body.len() - segment;
+    }
+
+    let mut visited = grid.blocked.clone();
+    visited[index_of(&head)] = true;
+    let mut queue = VecDeque::from([(head, 0_usize)]);
+
+    while let Some((point, moves)) = queue.pop_front() {
+        for (_, next) in grid.neighbours(point) {
+            // The tail always moves away before the head gets there
+            if next == tail {
+                return true;
+            }
+            if !visited[index_of(&next)] && moves + 1 >= free_after[index_of(&next)] {
+                visited[index_of(&next)] = true;
+                queue.push_back((next, moves + 1));
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::point::Topology;
+    use pretty_assertions::assert_eq;
+
+    /// Snake going down from `(y, x)`, head first
+    fn vertical_snake(y: u16, x: u16, length: u16) -> Vec<Point> {
+        (0..length).map(|i| Point::new(y + i, x)).collect()
+    }
+
+    const TOPOLOGIES: [Topology; 6] = [
+        Topology::TORUS,
+        Topology::BOX,
+        Topology::CYLINDER,
+        Topology::MOBIUS,
+        Topology::KLEIN_BOTTLE,
+        Topology::PROJECTIVE_PLANE,
+    ];
+
+    /// Columns of blocked cells at every `x`, except for rows in `gaps`
+    fn walls(xs: &[u16], gaps: &[u16]) -> Grid<'static> {
+        let points: Vec<Point> = xs
+            .iter()
+            .flat_map(|&x| (0..BOARD_SIZE_Y).map(move |y| Point::new(y, x)))
+            .filter(|point| !gaps.contains(&point.y))
+            .collect();
+
+        points.iter().collect()
+    }
+
+    /// Arena of `topology` with portals between opposite corners of the board
+    fn with_portals(topology: Topology) -> Arena {
+        let mut arena = Arena::new(topology);
+        arena
+            .add_portals(
+                Point::new(2, 2),
+                Point::new(BOARD_SIZE_Y - 3, BOARD_SIZE_X - 3),
+            )
+            .unwrap();
+        arena
+            .add_portals(
+                Point::new(2, BOARD_SIZE_X - 3),
+                Point::new(BOARD_SIZE_Y - 3, 2),
+            )
+            .unwrap();
+
+        arena
+    }
+
+    fn walk(grid: &Grid, start: Point, path: &[Direction]) -> Point {
+        path.iter().fold(start, |point, &direction| {
+            grid.neighbour(point, direction).unwrap()
+        })
+    }
+
+    #[test]
+    fn test_neighbours_wrap_around_corners() {
+        let corner = Point::new(0, 0);
+
+        assert_eq!(
+            Grid::default().neighbours(corner).collect::<Vec<_>>(),
+            vec![
+                (Direction::Up, Point::new(BOARD_SIZE_Y - 1, 0)),
+                (Direction::Down, Point::new(1, 0)),
+                (Direction::Left, Point::new(0, BOARD_SIZE_X - 1)),
+                (Direction::Right, Point::new(0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_box_corner_has_two_neighbours() {
+        let arena = Arena::new(Topology::BOX);
+        let grid = Grid::new(&arena);
+
+        assert_eq!(
+            grid.neighbours(Point::new(0, 0)).collect::<Vec<_>>(),
+            vec![
+                (Direction::Down, Point::new(1, 0)),
+                (Direction::Right, Point::new(0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_distance_takes_shorter_way_around() {
+        let grid = Grid::default();
+
+        assert_eq!(grid.distance(&Point::new(2, 3), &Point::new(2, 3)), 0);
+        assert_eq!(grid.distance(&Point::new(2, 3), &Point::new(5, 7)), 7);
+        assert_eq!(
+            grid.distance(
+                &Point::new(0, 0),
+                &Point::new(BOARD_SIZE_Y - 1, BOARD_SIZE_X - 1)
+            ),
+            2
+        );
+        assert_eq!(
+            grid.distance(
+                &Point::new(0, 0),
+                &Point::new(BOARD_SIZE_Y / 2, BOARD_SIZE_X / 2)
+            ),
+            (BOARD_SIZE_Y + BOARD_SIZE_X) / 2
+        );
+    }
+
+    #[test]
+    fn test_distance_in_box_does_not_wrap() {
+        let arena = Arena::new(Topology::BOX);
+        let grid = Grid::new(&arena);
+
+        assert_eq!(
+            grid.distance(
+                &Point::new(0, 0),
+                &Point::new(BOARD_SIZE_Y - 1, BOARD_SIZE_X - 1)
+            ),
+            BOARD_SIZE_Y + BOARD_SIZE_X - 2
+        );
+    }
+
+    #[test]
+    fn test_distance_never_overestimates() {
+        let start = Point::new(2, 31);
+
+        for topology in TOPOLOGIES {
+            let arena = Arena::new(topology);
+            let grid = Grid::new(&arena);
+            let map = DistanceMap::new(start, &grid);
+
+            assert_eq!(map.reachable().count(), CELLS, "{topology}");
+            for (point, moves) in map.reachable() {
+                let estimate = grid.distance(&start, &point);
+                assert!(
+                    estimate <= moves,
+                    "{topology}: {estimate} to {point:?}, {moves} moves"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_from_points() {
+        let mut grid: Grid = [Point::new(1, 2)].iter().collect();
+
+        assert!(grid.is_blocked(&Point::new(1, 2)));
+        assert!(!grid.is_blocked(&Point::new(2, 1)));
+
+        grid.unblock(&Point::new(1, 2));
+        assert_eq!(grid, Grid::default());
+    }
+
+    #[test]
+    fn test_bfs_finds_nearest_goal() {
+        let start = Point::new(5, 5);
+        let goals = [Point::new(5, 9), Point::new(8, 5)];
+
+        let path = bfs(start, &Grid::default(), |point| goals.contains(point)).unwrap();
+
+        assert_eq!(path, vec![Direction::Down; 3]);
+    }
+
+    #[test]
+    fn test_bfs_at_goal_is_empty_path() {
+        let start = Point::new(5, 5);
+
+        assert_eq!(
+            bfs(start, &Grid::default(), |point| *point == start),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_bfs_through_wall() {
+        let start = Point::new(0, 5);
+        let goal = Point::new(BOARD_SIZE_Y - 2, 5);
+
+        let path = bfs(start, &Grid::default(), |point| *point == goal).unwrap();
+
+        assert_eq!(path, vec![Direction::Up; 2]);
+    }
+
+    #[test]
+    fn test_bfs_goes_around_obstacles() {
+        // Only gap in the wall is at the bottom row
+        let obstacles = walls(&[5], &[BOARD_SIZE_Y - 1]);
+        let start = Point::new(0, 4);
+        let goal = Point::new(0, 6);
+
+        let path = bfs(start, &obstacles, |point| *point == goal).unwrap();
+
+        // Up through the top wall to the bottom row, across the gap and back up
+        assert_eq!(path.len(), 4);
+        assert_eq!(walk(&obstacles, start, &path), goal);
+    }
+
+    #[test]
+    fn test_bfs_starts_from_obstacle() {
+        let start = Point::new(5, 5);
+        let obstacles: Grid = [start].iter().collect();
+
+        let path = bfs(start, &obstacles, |point| *point == Point::new(5, 6));
+
+        assert_eq!(path, Some(vec![Direction::Right]));
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        // Two full walls cut the board into separate strips, since the board wraps around
+        let obstacles = walls(&[5, 10], &[]);
+        let start = Point::new(3, 7);
+        let goal = Point::new(3, 12);
+
+        assert_eq!(bfs(start, &obstacles, |point| *point == goal), None);
+        assert_eq!(a_star(start, goal, &obstacles), None);
+    }
+
+    #[test]
+    fn test_a_star_matches_bfs_length() {
+        let obstacles = walls(&[5], &[3, BOARD_SIZE_Y - 4]);
+        let start = Point::new(10, 2);
+
+        for goal in [
+            Point::new(10, 8),
+            Point::new(3, 5),
+            Point::new(BOARD_SIZE_Y - 1, BOARD_SIZE_X - 1),
+            Point::new(17, 30),
+        ] {
+            let shortest = bfs(start, &obstacles, |point| *point == goal).unwrap();
+            let path = a_star(start, goal, &obstacles).unwrap();
+
+            assert_eq!(path.len(), shortest.len(), "path to {goal:?}");
+            assert_eq!(walk(&obstacles, start, &path), goal);
+        }
+    }
+
+    #[test]
+    fn test_a_star_matches_bfs_length_on_every_topology() {
+        let start = Point::new(3, 3);
+
+        for topology in TOPOLOGIES {
+            let arena = Arena::new(topology);
+            let mut grid = walls(&[8, 20], &[0, 10]);
+            grid.arena = &arena;
+            let map = DistanceMap::new(start, &grid);
+
+            for (goal, moves) in map.reachable().step_by(7) {
+                let path = a_star(start, goal, &grid).unwrap();
+
+                assert_eq!(path.len(), moves as usize, "{topology}: path to {goal:?}");
+                assert_eq!(walk(&grid, start, &path), goal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bfs_takes_mirrored_way_around() {
+        let arena = Arena::new(Topology::KLEIN_BOTTLE);
+        let grid = Grid::new(&arena);
+        let start = Point::new(1, 0);
+        let goal = Point::new(BOARD_SIZE_Y - 2, BOARD_SIZE_X - 1);
+
+        let path = bfs(start, &grid, |point| *point == goal).unwrap();
+
+        assert_eq!(path, vec![Direction::Left]);
+    }
+
+    #[test]
+    fn test_a_star_wraps_around() {
+        let start = Point::new(1, 1);
+        let goal = Point::new(BOARD_SIZE_Y - 1, BOARD_SIZE_X - 1);
+
+        let grid = Grid::default();
+
+        let path = a_star(start, goal, &grid).unwrap();
+
+        assert_eq!(path.len(), 4);
+        assert_eq!(walk(&grid, start, &path), goal);
+    }
+
+    #[test]
+    fn test_distance_map() {
+        let obstacles: Grid = [Point::new(5, 6)].iter().collect();
+        let map = DistanceMap::new(Point::new(5, 5), &obstacles);
+
+        assert_eq!(map.get(&Point::new(5, 5)), Some(0));
+        assert_eq!(map.get(&Point::new(4, 5)), Some(1));
+        assert_eq!(map.get(&Point::new(5, 6)), None);
+        // Around the obstacle
+        assert_eq!(map.get(&Point::new(5, 7)), Some(4));
+        // Through the left wall
+        assert_eq!(map.get(&Point::new(5, BOARD_SIZE_X - 1)), Some(6));
+        assert_eq!(map.reachable().count(), CELLS - 1);
+    }
+
+    #[test]
+    fn test_distance_map_agrees_with_distance_on_empty_board() {
+        let start = Point::new(3, 17);
+        for topology in [Topology::TORUS, Topology::BOX, Topology::CYLINDER] {
+            let arena = Arena::new(topology);
+            let grid = Grid::new(&arena);
+            let map = DistanceMap::new(start, &grid);
+
+            for (point, moves) in map.reachable() {
+                assert_eq!(
+                    moves,
+                    grid.distance(&start, &point),
+                    "{topology}: {point:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_region_size() {
+        assert_eq!(region_size(Point::new(0, 0), &Grid::default()), CELLS);
+
+        // Strip between two walls, which wraps around vertically
+        let obstacles = walls(&[5, 8], &[]);
+        assert_eq!(
+            region_size(Point::new(0, 6), &obstacles),
+            2 * BOARD_SIZE_Y as usize
+        );
+    }
+
+    #[test]
+    fn test_region_size_in_box() {
+        // Strip between two walls, which is cut off at the top and bottom
+        let arena = Arena::new(Topology::BOX);
+        let mut grid = walls(&[5, 8], &[]);
+        grid.arena = &arena;
+        grid.block(&Point::new(10, 6));
+        grid.block(&Point::new(10, 7));
+
+        assert_eq!(region_size(Point::new(0, 6), &grid), 20);
+    }
+
+    #[test]
+    fn test_region_size_from_enclosed_cell() {
+        let start = Point::new(5, 5);
+        let obstacles: Grid = Grid::default()
+            .neighbours(start)
+            .map(|(_, point)| point)
+            .collect::<Vec<_>>()
+            .iter()
+            .collect();
+
+        assert_eq!(region_size(start, &obstacles), 0);
+    }
+
+    #[test]
+    fn test_straight_snake_reaches_tail() {
+        assert!(can_reach_tail(&vertical_snake(5, 5, 4), &Grid::default()));
+    }
+
+    /// Head at (5, 5) with the body coiled around it and the tail next to it
+    fn coiled_snake() -> Vec<Point> {
+        vec![
+            Point::new(5, 5),
+            Point::new(4, 5),
+            Point::new(4, 6),
+            Point::new(5, 6),
+            Point::new(6, 6),
+            Point::new(6, 5),
+            Point::new(6, 4),
+            Point::new(5, 4),
+        ]
+    }
+
+    #[test]
+    fn test_coiled_snake_reaches_tail_as_it_moves_away() {
+        assert!(can_reach_tail(&coiled_snake(), &Grid::default()));
+    }
+
+    #[test]
+    fn test_snake_trapped_by_its_own_body() {
+        // Same coil with a long tail, none of the segments around the head move away in time
+        let mut body = coiled_snake();
+        body.extend((0..22).map(|i| Point::new(4, (BOARD_SIZE_X + 4 - i) % BOARD_SIZE_X)));
+
+        assert!(!can_reach_tail(&body, &Grid::default()));
+    }
+
+    #[test]
+    fn test_tail_across_edge_is_reached_only_through_it() {
+        // Head boxed in, apart from the left edge, with the tail on the other side of it
+        let body = [Point::new(5, 0), Point::new(5, BOARD_SIZE_X - 1)];
+        let boxed_in = [Point::new(4, 0), Point::new(6, 0), Point::new(5, 1)];
+
+        let arena = Arena::new(Topology::CYLINDER);
+        let mut grid = Grid::new(&arena);
+        grid.extend(&boxed_in);
+        assert!(can_reach_tail(&body, &grid));
+
+        let arena = Arena::new(Topology::BOX);
+        let mut grid = Grid::new(&arena);
+        grid.extend(&boxed_in);
+        assert!(!can_reach_tail(&body, &grid));
+    }
+
+    #[test]
+    fn test_empty_snake_has_no_tail() {
+        assert!(!can_reach_tail(&[], &Grid::default()));
+    }
+
+    #[test]
+    fn test_bfs_takes_portal() {
+        let arena = with_portals(Topology::BOX);
+        let grid = Grid::new(&arena);
+        let start = Point::new(2, 1);
+        let goal = Point::new(BOARD_SIZE_Y - 3, BOARD_SIZE_X - 1);
+
+        let path = bfs(start, &grid, |point| *point == goal).unwrap();
+
+        assert_eq!(path, vec![Direction::Right, Direction::Right]);
+        assert_eq!(grid.distance(&start, &goal), 2);
+    }
+
+    #[test]
+    fn test_portal_cells_are_never_neighbours() {
+        let arena = with_portals(Topology::TORUS);
+        let grid = Grid::new(&arena);
+        let map = DistanceMap::new(Point::new(10, 10), &grid);
+
+        assert_eq!(map.get(&Point::new(2, 2)), None);
+        assert_eq!(map.reachable().count(), CELLS - 4);
+    }
+
+    #[test]
+    fn test_a_star_matches_bfs_length_through_portals() {
+        let start = Point::new(3, 3);
+
+        for topology in TOPOLOGIES {
+            let arena = with_portals(topology);
+            let mut grid = walls(&[8, 20], &[0, 10]);
+            grid.arena = &arena;
+            let map = DistanceMap::new(start, &grid);
+
+            for (goal, moves) in map.reachable() {
+                assert!(
+                    grid.distance(&start, &goal) <= moves,
+                    "{topology}: {goal:?}"
+                );
+                if goal.x % 3 == 0 {
+                    let path = a_star(start, goal, &grid).unwrap();
+
+                    assert_eq!(path.len(), moves as usize, "{topology}: path to {goal:?}");
+                    assert_eq!(walk(&grid, start, &path), goal);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,489 @@
+use super::analysis::{DistanceMap, Grid};
+use super::board::get_center_of_board_coordinates;
+use super::consts::*;
+use super::layout::Layout;
+use super::point::{Direction, Point, Topology};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+use tracing::warn;
+
+/// Cells ahead of the snake's head at the start which are kept free of walls
+const SPAWN_CLEARANCE: u16 = 3;
+/// Random pairs of cells tried for each pair of portals before giving up
+const PORTAL_ATTEMPTS: usize = 200;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ArenaError {
+    #[error("{0:?} is outside of the board")]
+    OutsideOfBoard(Point),
+
+    #[error("Portal at {0:?} is next to another portal, portals need free cells around them")]
+    PortalsTooClose(Point),
+
+    #[error("{0:?} is where the snake starts")]
+    OnSnakeSpawn(Point),
+
+    #[error("{0:?} is already taken by a wall or a portal")]
+    Occupied(Point),
+
+    #[error("{0:?} would not be reachable from where the snake starts")]
+    Unreachable(Point),
+
+    #[error("At most {MAX_PORTAL_PAIRS} pairs of portals are allowed, got {0}")]
+    TooManyPortals(usize),
+
+    #[error("There is no room left for another pair of portals")]
+    NoRoomForPortals,
+}
+
+/// Layout of the board a game is played on, how its edges behave and what is placed inside them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Arena {
+    topology: Topology,
+    /// Pairs of portal cells, a head entering either one comes out of the other
+    portals: Vec<(Point, Point)>,
+    /// Cells inside the board the snake dies on
+    walls: HashSet<Point>,
+}
+
+impl Arena {
+    pub fn new(topology: Topology) -> Self {
+        Self {
+            topology,
+            portals: vec![],
+            walls: HashSet::new(),
+        }
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn portals(&self) -> &[(Point, Point)] {
+        &self.portals
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = &Point> {
+        self.walls.iter()
+    }
+
+    pub fn is_wall(&self, point: &Point) -> bool {
+        self.walls.contains(point)
+    }
+
+    /// Whether there is neither a wall nor a portal at `point`, so fruits can be placed there
+    pub fn is_free(&self, point: &Point) -> bool {
+        !self.is_wall(point) && !self.is_portal(point)
+    }
+
+    /// Whether `point` is taken by the snake at the start or lies right ahead of it, which is
+    /// where neither walls nor portals are ever put
+    pub fn is_spawn_zone(point: &Point) -> bool {
+        let center = get_center_of_board_coordinates();
+        point.x == center.x
+            && (center.y - SPAWN_CLEARANCE..center.y + SPAWN_LENGTH).contains(&point.y)
+    }
+
+    fn check_inside(point: Point) -> Result<(), ArenaError> {
+        match point.y < BOARD_SIZE_Y && point.x < BOARD_SIZE_X {
+            true => Ok(()),
+            false => Err(ArenaError::OutsideOfBoard(point)),
+        }
+    }
+
+    /// First free cell the snake cannot get to from where it starts, found by flooding the
+    /// board from its head
+    pub fn unreachable_cell(&self) -> Option<Point> {
+        let reachable = DistanceMap::new(get_center_of_board_coordinates(), &Grid::new(self));
+
+        (0..BOARD_SIZE_Y)
+            .flat_map(|y| (0..BOARD_SIZE_X).map(move |x| Point::new(y, x)))
+            .find(|point| self.is_free(point) && reachable.get(point).is_none())
+    }
+
+    /// Puts up walls at `points`, unless that cuts off any free cell from the rest of the board
+    pub fn add_walls(&mut self, points: impl IntoIterator<Item = Point>) -> Result<(), ArenaError> {
+        let points: Vec<Point> = points.into_iter().collect();
+        for &point in &points {
+            Self::check_inside(point)?;
+            if Self::is_spawn_zone(&point) {
+                return Err(ArenaError::OnSnakeSpawn(point));
+            }
+            if !self.is_free(&point) {
+                return Err(ArenaError::Occupied(point));
+            }
+        }
+
+        self.walls.extend(&points);
+        if let Some(unreachable) = self.unreachable_cell() {
+            for point in &points {
+                self.walls.remove(point);
+            }
+            return Err(ArenaError::Unreachable(unreachable));
+        }
+
+        Ok(())
+    }
+
+    /// Whether `point` and `other` are the same or neighbouring cells
+    fn touching(&self, point: &Point, other: &Point) -> bool {
+        point == other
+            || DIRECTIONS
+                .into_iter()
+                .any(|direction| self.topology.step(*point, direction) == Some(*other))
+    }
+
+    /// Checks whether a portal can be placed at `point`
+    fn check_portal(&self, point: Point) -> Result<(), ArenaError> {
+        Self::check_inside(point)?;
+        if Self::is_spawn_zone(&point) {
+            return Err(ArenaError::OnSnakeSpawn(point));
+        }
+        if self.is_wall(&point) {
+            return Err(ArenaError::Occupied(point));
+        }
+        // Nothing lands a head right onto another portal, so portals never chain
+        if self
+            .portal_cells()
+            .any(|portal| self.touching(&portal, &point))
+        {
+            return Err(ArenaError::PortalsTooClose(point));
+        }
+
+        Ok(())
+    }
+
+    /// Connects two cells with portals, each of them has to be surrounded by cells without one.
+    /// A portal skips the cell it is on, so it must not leave cells behind it unreachable
+    pub fn add_portals(&mut self, entrance: Point, exit: Point) -> Result<(), ArenaError> {
+        self.check_portal(entrance)?;
+        self.check_portal(exit)?;
+        if self.touching(&entrance, &exit) {
+            return Err(ArenaError::PortalsTooClose(exit));
+        }
+
+        self.portals.push((entrance, exit));
+        if let Some(unreachable) = self.unreachable_cell() {
+            self.portals.pop();
+            return Err(ArenaError::Unreachable(unreachable));
+        }
+
+        Ok(())
+    }
+
+    /// Places `pairs` more pairs of portals at random
+    pub fn add_random_portals(
+        &mut self,
+        pairs: usize,
+        rng: &mut impl Rng,
+    ) -> Result<(), ArenaError> {
+        if self.portals.len() + pairs > MAX_PORTAL_PAIRS {
+            return Err(ArenaError::TooManyPortals(self.portals.len() + pairs));
+        }
+
+        let candidates: Vec<Point> = (0..BOARD_SIZE_Y)
+            .flat_map(|y| (0..BOARD_SIZE_X).map(move |x| Point::new(y, x)))
+            .filter(|point| self.check_portal(*point).is_ok())
+            .collect();
+        for _ in 0..pairs {
+            let placed = (0..PORTAL_ATTEMPTS).any(|_| {
+                let pair: Vec<Point> = candidates.choose_multiple(rng, 2).copied().collect();
+                pair.len() == 2 && self.add_portals(pair[0], pair[1]).is_ok()
+            });
+            if !placed {
+                return Err(ArenaError::NoRoomForPortals);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn portal_cells(&self) -> impl Iterator<Item = Point> + '_ {
+        self.portals
+            .iter()
+            .flat_map(|(entrance, exit)| [*entrance, *exit])
+    }
+
+    pub fn is_portal(&self, point: &Point) -> bool {
+        self.portal_cells().any(|portal| portal == *point)
+    }
+
+    /// Other end of the portal at `point`
+    pub fn partner(&self, point: &Point) -> Option<Point> {
+        self.portals.iter().find_map(|(entrance, exit)| {
+            if entrance == point {
+                Some(*exit)
+            } else if exit == point {
+                Some(*entrance)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Cell the head moves onto from `point` going in `direction`, `None` when it runs into a
+    /// wall. Entering a portal leads out of its partner, on the cell past it in the same direction
+    pub fn step(&self, point: Point, direction: Direction) -> Option<Point> {
+        let next = self.topology.step(point, direction)?;
+        let next = match self.partner(&next) {
+            Some(exit) => self.topology.step(exit, direction)?,
+            None => next,
+        };
+
+        (!self.is_wall(&next)).then_some(next)
+    }
+}
+
+/// How arenas are laid out for each new game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArenaOptions {
+    topology: Topology,
+    layout: Layout,
+    portals: usize,
+    seed: Option<u64>,
+}
+
+impl ArenaOptions {
+    /// Arenas of `topology` with walls laid out by `layout` and `portals` pairs of portals placed
+    /// at random. With a `seed` every game gets the same arena, which is laid out once here so a
+    /// seed the layout fails for is rejected up front
+    pub fn new(
+        topology: Topology,
+        layout: Layout,
+        portals: usize,
+        seed: Option<u64>,
+    ) -> Result<Self, ArenaError> {
+        if portals > MAX_PORTAL_PAIRS {
+            return Err(ArenaError::TooManyPortals(portals));
+        }
+
+        let options = Self {
+            topology,
+            layout,
+            portals,
+            seed,
+        };
+        if let Some(seed) = seed {
+            options.lay_out(seed)?;
+        }
+
+        Ok(options)
+    }
+
+    /// Lays out a new arena, `rng` only picks the seed when the options have none. Seeds the
+    /// layout fails for are replaced by new ones
+    pub fn generate(&self, rng: &mut impl Rng) -> Arena {
+        loop {
+            let seed = self.seed.unwrap_or_else(|| rng.gen());
+            match self.lay_out(seed) {
+                Ok(arena) => return arena,
+                // A fixed seed was already laid out in `new`, so only random ones end up here
+                Err(err) => warn!(
+                    "Laying out {} arena of seed {} failed, trying another seed: {}",
+                    self.layout, seed, err
+                ),
+            }
+        }
+    }
+
+    fn lay_out(&self, seed: u64) -> Result<Arena, ArenaError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut arena = Arena::new(self.topology);
+        self.layout.lay_out(&mut arena, &mut rng)?;
+        if let Err(err) = arena.add_random_portals(self.portals, &mut rng) {
+            warn!(
+                "Placed {} of {} pairs of portals in arena of seed {}: {}",
+                arena.portals().len(),
+                self.portals,
+                seed,
+                err
+            );
+        }
+
+        Ok(arena)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_head_comes_out_past_partner_keeping_direction() {
+        let mut arena = Arena::new(Topology::TORUS);
+        arena
+            .add_portals(Point::new(2, 2), Point::new(10, 30))
+            .unwrap();
+
+        assert_eq!(
+            arena.step(Point::new(2, 1), Direction::Right),
+            Some(Point::new(10, 31))
+        );
+        assert_eq!(
+            arena.step(Point::new(11, 30), Direction::Up),
+            Some(Point::new(1, 2))
+        );
+        assert_eq!(
+            arena.step(Point::new(5, 5), Direction::Left),
+            Some(Point::new(5, 4))
+        );
+    }
+
+    #[test]
+    fn test_leaving_portal_into_wall() {
+        let mut arena = Arena::new(Topology::BOX);
+        arena
+            .add_portals(Point::new(2, 2), Point::new(0, 30))
+            .unwrap();
+
+        assert_eq!(arena.step(Point::new(3, 2), Direction::Up), None);
+        assert_eq!(
+            arena.step(Point::new(1, 30), Direction::Up),
+            Some(Point::new(1, 2))
+        );
+    }
+
+    #[test]
+    fn test_invalid_portals_are_rejected() {
+        let center = get_center_of_board_coordinates();
+        let mut arena = Arena::new(Topology::TORUS);
+        arena
+            .add_portals(Point::new(2, 2), Point::new(10, 30))
+            .unwrap();
+
+        assert_eq!(
+            arena.add_portals(Point::new(2, 3), Point::new(15, 5)),
+            Err(ArenaError::PortalsTooClose(Point::new(2, 3)))
+        );
+        assert_eq!(
+            arena.add_portals(Point::new(4, 4), Point::new(4, 5)),
+            Err(ArenaError::PortalsTooClose(Point::new(4, 5)))
+        );
+        assert_eq!(
+            arena.add_portals(Point::new(4, 4), center),
+            Err(ArenaError::OnSnakeSpawn(center))
+        );
+        let ahead = Point::new(center.y - SPAWN_CLEARANCE, center.x);
+        assert_eq!(
+            arena.add_portals(ahead, Point::new(4, 4)),
+            Err(ArenaError::OnSnakeSpawn(ahead))
+        );
+        assert_eq!(
+            arena.add_portals(Point::new(4, 4), Point::new(BOARD_SIZE_Y, 0)),
+            Err(ArenaError::OutsideOfBoard(Point::new(BOARD_SIZE_Y, 0)))
+        );
+        assert_eq!(arena.portals().len(), 1);
+    }
+
+    #[test]
+    fn test_random_portals_keep_apart() {
+        let options = ArenaOptions::new(
+            Topology::KLEIN_BOTTLE,
+            Layout::Empty,
+            MAX_PORTAL_PAIRS,
+            None,
+        )
+        .unwrap();
+
+        let arena = options.generate(&mut StdRng::seed_from_u64(7));
+
+        assert_eq!(arena.portals().len(), MAX_PORTAL_PAIRS);
+        let cells: Vec<Point> = arena.portal_cells().collect();
+        for (i, portal) in cells.iter().enumerate() {
+            assert!(cells[i + 1..]
+                .iter()
+                .all(|other| !arena.touching(portal, other)));
+            assert!(!Arena::is_spawn_zone(portal));
+        }
+    }
+
+    #[test]
+    fn test_too_many_portals() {
+        assert_eq!(
+            ArenaOptions::new(Topology::TORUS, Layout::Empty, MAX_PORTAL_PAIRS + 1, None),
+            Err(ArenaError::TooManyPortals(MAX_PORTAL_PAIRS + 1))
+        );
+    }
+
+    #[test]
+    fn test_head_runs_into_walls() {
+        let mut arena = Arena::new(Topology::TORUS);
+        arena.add_walls([Point::new(5, 5)]).unwrap();
+        arena
+            .add_portals(Point::new(2, 2), Point::new(4, 5))
+            .unwrap();
+
+        assert_eq!(arena.step(Point::new(5, 4), Direction::Right), None);
+        // Out of the portal right onto the wall below it
+        assert_eq!(arena.step(Point::new(1, 2), Direction::Down), None);
+        assert!(!arena.is_free(&Point::new(5, 5)));
+        assert!(!arena.is_free(&Point::new(4, 5)));
+    }
+
+    #[test]
+    fn test_walls_cutting_off_cells_are_rejected() {
+        let center = get_center_of_board_coordinates();
+        let mut arena = Arena::new(Topology::TORUS);
+        arena
+            .add_walls([Point::new(2, 3), Point::new(4, 3), Point::new(3, 2)])
+            .unwrap();
+
+        assert_eq!(
+            arena.add_walls([Point::new(3, 4), Point::new(8, 8)]),
+            Err(ArenaError::Unreachable(Point::new(3, 3)))
+        );
+        assert!(!arena.is_wall(&Point::new(8, 8)));
+        assert_eq!(
+            arena.add_walls([Point::new(center.y - 1, center.x)]),
+            Err(ArenaError::OnSnakeSpawn(Point::new(center.y - 1, center.x)))
+        );
+        assert_eq!(
+            arena.add_walls([Point::new(2, 3)]),
+            Err(ArenaError::Occupied(Point::new(2, 3)))
+        );
+        assert_eq!(arena.walls().count(), 3);
+    }
+
+    #[test]
+    fn test_portal_cutting_off_dead_end_is_rejected() {
+        // The leftmost column can only be entered from the bottom row, with both ends of the
+        // portal in it the snake always jumps over the cells between them
+        let mut arena = Arena::new(Topology::BOX);
+        arena
+            .add_walls((0..BOARD_SIZE_Y - 1).map(|y| Point::new(y, 1)))
+            .unwrap();
+
+        assert_eq!(
+            arena.add_portals(Point::new(10, 0), Point::new(5, 0)),
+            Err(ArenaError::Unreachable(Point::new(6, 0)))
+        );
+        assert_eq!(arena.portals(), &[]);
+    }
+
+    #[test]
+    fn test_seeded_arenas_are_the_same_every_game() {
+        let options = ArenaOptions::new(Topology::BOX, Layout::Maze, 4, Some(11)).unwrap();
+
+        let arena = options.generate(&mut StdRng::seed_from_u64(1));
+
+        assert_eq!(arena, options.generate(&mut StdRng::seed_from_u64(2)));
+        assert_eq!(arena.portals().len(), 4);
+        assert_eq!(arena.unreachable_cell(), None);
+    }
+}
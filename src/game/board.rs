@@ -1,7 +1,11 @@
+use super::arena::Arena;
+use super::campaign::Level;
 use super::consts::*;
-use super::point::Point;
+use super::point::{Edge, Point};
+use super::render::{PlainRenderer, Renderer};
 
 use std::fmt::{Display, Write};
+use std::sync::Arc;
 const CANVAS_SIZE_X: usize = BOARD_SIZE_X as usize + 2;
 const CANVAS_SIZE_Y: usize = BOARD_SIZE_Y as usize + 2;
 
@@ -12,8 +16,13 @@ const NE: char = '└';
 
 const NS: char = '│';
 const EW: char = '─';
+const SOLID_NS: char = '┃';
+const SOLID_EW: char = '━';
+const MIRROR_NS: char = '║';
+const MIRROR_EW: char = '═';
+const BLOCK: char = '█';
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Junction {
     NE,
     NW,
@@ -32,10 +41,38 @@ impl From<Junction> for char {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Border of the board, the snake passes through walls drawn as thin lines and dies on solid ones,
+/// as well as on blocks put up inside the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Wall {
     NS,
     EW,
+    SolidNS,
+    SolidEW,
+    /// Edges the snake comes back mirrored through
+    MirrorNS,
+    MirrorEW,
+    Block,
+}
+
+impl Wall {
+    /// Wall drawn along edges crossed moving left or right
+    fn ns(edge: Edge) -> Self {
+        match edge {
+            Edge::Wrap => Wall::NS,
+            Edge::Mirror => Wall::MirrorNS,
+            Edge::Wall => Wall::SolidNS,
+        }
+    }
+
+    /// Wall drawn along edges crossed moving up or down
+    fn ew(edge: Edge) -> Self {
+        match edge {
+            Edge::Wrap => Wall::EW,
+            Edge::Mirror => Wall::MirrorEW,
+            Edge::Wall => Wall::SolidEW,
+        }
+    }
 }
 
 impl From<Wall> for char {
@@ -43,11 +80,16 @@ impl From<Wall> for char {
         match w {
             Wall::NS => NS,
             Wall::EW => EW,
+            Wall::SolidNS => SOLID_NS,
+            Wall::SolidEW => SOLID_EW,
+            Wall::MirrorNS => MIRROR_NS,
+            Wall::MirrorEW => MIRROR_EW,
+            Wall::Block => BLOCK,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CellSymbol {
     Board,
     Snake,
@@ -55,10 +97,12 @@ pub enum CellSymbol {
     Fruit,
     Wall(Wall),
     Junction(Junction),
+    /// Either end of a pair of portals, numbered in the order they were placed
+    Portal(u8),
 }
 
 impl CellSymbol {
-    fn to_char(self) -> char {
+    pub(super) fn to_char(self) -> char {
         match self {
             CellSymbol::Board => ' ',
             CellSymbol::Snake => '#',
@@ -66,6 +110,7 @@ impl CellSymbol {
             CellSymbol::Fruit => 'O',
             CellSymbol::Wall(wall) => wall.into(),
             CellSymbol::Junction(junction) => junction.into(),
+            CellSymbol::Portal(pair) => (b'A' + pair % 26) as char,
         }
     }
 }
@@ -78,15 +123,14 @@ impl Display for CellSymbol {
 }
 
 pub fn get_center_of_board_coordinates() -> Point {
-    let y = BOARD_SIZE_Y / 2 + (BOARD_SIZE_Y % 2 != 0) as u16 - 1;
-    let x = BOARD_SIZE_X / 2 + (BOARD_SIZE_X % 2 != 0) as u16 - 1;
+    let y = BOARD_SIZE_Y / 2 + !BOARD_SIZE_Y.is_multiple_of(2) as u16 - 1;
+    let x = BOARD_SIZE_X / 2 + !BOARD_SIZE_X.is_multiple_of(2) as u16 - 1;
 
     Point::new(y, x)
 }
 
 pub fn generate_points_pool() -> Vec<Point> {
     (0..BOARD_SIZE_Y)
-        .into_iter()
         .flat_map(|y| {
             let copy_y = y;
             (0..BOARD_SIZE_X).map(move |x| Point { x, y: copy_y })
@@ -96,30 +140,114 @@ pub fn generate_points_pool() -> Vec<Point> {
 
 type Canvas = [[CellSymbol; CANVAS_SIZE_X]; CANVAS_SIZE_Y];
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Board {
-    // This could be a RwLock,
-    // so it would avoid reading partial updates
     canvas: Canvas,
+    score: u32,
+    level: Option<Level>,
+    arena: Arc<Arena>,
 }
 
 impl Board {
+    /// Empty board, its border shows how the snake crosses each edge
+    pub fn new(arena: Arc<Arena>) -> Board {
+        let topology = arena.topology();
+        let mut canvas = [[CellSymbol::Board; CANVAS_SIZE_X]; CANVAS_SIZE_Y];
+
+        for row in canvas.iter_mut().take(CANVAS_SIZE_Y - 1).skip(1) {
+            // set '|' for vertical walls
+            row[0] = CellSymbol::Wall(Wall::ns(topology.horizontal));
+            row[CANVAS_SIZE_X - 1] = CellSymbol::Wall(Wall::ns(topology.horizontal));
+        }
+
+        // set '-' for horizontal walls
+        for row in [0, CANVAS_SIZE_Y - 1] {
+            canvas[row][1..(CANVAS_SIZE_X - 1)].fill(CellSymbol::Wall(Wall::ew(topology.vertical)));
+        }
+
+        // set proper symbol for corner cells
+        canvas[0][0] = CellSymbol::Junction(Junction::SE);
+        canvas[CANVAS_SIZE_Y - 1][CANVAS_SIZE_X - 1] = CellSymbol::Junction(Junction::NW);
+        canvas[0][CANVAS_SIZE_X - 1] = CellSymbol::Junction(Junction::SW);
+        canvas[CANVAS_SIZE_Y - 1][0] = CellSymbol::Junction(Junction::NE);
+
+        // Walls and portals are drawn once, nothing else is ever placed on them
+        for wall in arena.walls() {
+            canvas[wall.y as usize + 1][wall.x as usize + 1] = CellSymbol::Wall(Wall::Block);
+        }
+        for (pair, (entrance, exit)) in arena.portals().iter().enumerate() {
+            for portal in [entrance, exit] {
+                canvas[portal.y as usize + 1][portal.x as usize + 1] =
+                    CellSymbol::Portal(pair as u8);
+            }
+        }
+
+        Board {
+            canvas,
+            score: 0,
+            level: None,
+            arena,
+        }
+    }
+
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
     pub fn get_board(&self, wr: &mut impl Write) -> Result<(), std::fmt::Error> {
+        self.render(&PlainRenderer, wr)
+    }
+
+    pub fn render(
+        &self,
+        renderer: &dyn Renderer,
+        wr: &mut impl Write,
+    ) -> Result<(), std::fmt::Error> {
         for row in self.canvas {
             for cell in row {
-                wr.write_char(cell.to_char())?;
+                renderer.write_cell(wr, cell)?;
             }
-            wr.write_char('\n')?;
+            renderer.write_line_end(wr)?;
         }
 
         Ok(())
     }
 
+    pub fn rows(&self) -> impl Iterator<Item = &[CellSymbol]> {
+        self.canvas.iter().map(|row| row.as_slice())
+    }
+
+    pub fn canvas_size() -> (usize, usize) {
+        (CANVAS_SIZE_Y, CANVAS_SIZE_X)
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn set_score(&mut self, score: u32) {
+        self.score = score;
+    }
+
+    /// Stage of the campaign played on this board, if any
+    pub fn level(&self) -> Option<&Level> {
+        self.level.as_ref()
+    }
+
+    pub fn set_level(&mut self, level: Option<Level>) {
+        self.level = level;
+    }
+
     fn translate_points_to_cavas_points(&self, point: &Point) -> (usize, usize) {
         let (y, x) = point.get_coords();
         (y as usize + 1, x as usize + 1)
     }
 
+    pub fn cell_symbol(&self, point: &Point) -> CellSymbol {
+        let (y, x) = self.translate_points_to_cavas_points(point);
+        self.canvas[y][x]
+    }
+
     pub fn change_cell_symbol(&mut self, point: &Point, symbol: CellSymbol) {
         let (y, x) = self.translate_points_to_cavas_points(point);
         self.canvas[y][x] = symbol;
@@ -128,33 +256,14 @@ impl Board {
 
 impl Default for Board {
     fn default() -> Board {
-        let mut canvas = [[CellSymbol::Board; CANVAS_SIZE_X]; CANVAS_SIZE_Y];
-
-        for i in 1..(CANVAS_SIZE_Y - 1) {
-            // set '|' for vertical walls
-            canvas[i][0] = CellSymbol::Wall(Wall::NS);
-            canvas[i][CANVAS_SIZE_X - 1] = CellSymbol::Wall(Wall::NS);
-        }
-
-        for i in 1..(CANVAS_SIZE_X - 1) {
-            // set '-' for horizontal walls
-            canvas[0][i] = CellSymbol::Wall(Wall::EW);
-            canvas[CANVAS_SIZE_Y - 1][i] = CellSymbol::Wall(Wall::EW);
-        }
-
-        // set proper symbol for corner cells
-        canvas[0][0] = CellSymbol::Junction(Junction::SE);
-        canvas[CANVAS_SIZE_Y - 1][CANVAS_SIZE_X - 1] = CellSymbol::Junction(Junction::NW);
-        canvas[0][CANVAS_SIZE_X - 1] = CellSymbol::Junction(Junction::SW);
-        canvas[CANVAS_SIZE_Y - 1][0] = CellSymbol::Junction(Junction::NE);
-
-        Board { canvas }
+        Board::new(Arc::default())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::point::Topology;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -189,4 +298,64 @@ mod tests {
 
         assert_eq!(raw_canvas, generated_board_str.as_str());
     }
+
+    #[test]
+    fn test_cylinder_border() {
+        let board = Board::new(Arc::new(Arena::new(Topology::CYLINDER)));
+        let rows: Vec<String> = board
+            .rows()
+            .map(|row| row.iter().map(|cell| cell.to_char()).collect())
+            .collect();
+
+        assert_eq!(rows[0], format!("┌{}┐", "━".repeat(BOARD_SIZE_X as usize)));
+        assert_eq!(rows[1], format!("│{}│", " ".repeat(BOARD_SIZE_X as usize)));
+        assert_eq!(board.arena().topology(), Topology::CYLINDER);
+    }
+
+    #[test]
+    fn test_mirrored_edges_are_drawn_doubled() {
+        let board = Board::new(Arc::new(Arena::new(Topology::KLEIN_BOTTLE)));
+
+        assert_eq!(board.cell_symbol(&Point::new(0, 0)), CellSymbol::Board);
+        assert_eq!(
+            board.rows().nth(1).unwrap()[0],
+            CellSymbol::Wall(Wall::MirrorNS)
+        );
+        assert_eq!(board.rows().next().unwrap()[1], CellSymbol::Wall(Wall::EW));
+    }
+
+    #[test]
+    fn test_portals_are_drawn_with_letter_of_their_pair() {
+        let mut arena = Arena::new(Topology::TORUS);
+        arena
+            .add_portals(Point::new(0, 0), Point::new(5, 5))
+            .unwrap();
+        arena
+            .add_portals(Point::new(2, 7), Point::new(15, 20))
+            .unwrap();
+
+        let board = Board::new(Arc::new(arena));
+
+        assert_eq!(board.cell_symbol(&Point::new(5, 5)), CellSymbol::Portal(0));
+        assert_eq!(board.cell_symbol(&Point::new(2, 7)), CellSymbol::Portal(1));
+        assert_eq!(board.rows().nth(16).unwrap()[21].to_char(), 'B');
+    }
+
+    #[test]
+    fn test_walls_inside_board_are_drawn_as_blocks() {
+        let mut arena = Arena::new(Topology::TORUS);
+        arena
+            .add_walls([Point::new(0, 0), Point::new(0, 1)])
+            .unwrap();
+
+        let board = Board::new(Arc::new(arena));
+
+        assert_eq!(
+            board.rows().nth(1).unwrap()[..4]
+                .iter()
+                .map(|cell| cell.to_char())
+                .collect::<String>(),
+            "│██ "
+        );
+    }
 }
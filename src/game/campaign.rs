@@ -0,0 +1,287 @@
+//! Built-in stages played one after another, each one cleared by reaching its goal
+
+use super::arena::{Arena, ArenaError, ArenaOptions};
+use super::layout::Layout;
+use super::point::Topology;
+use super::runner::GameEnd;
+
+use rand::thread_rng;
+use std::sync::Arc;
+
+pub use snake_proto::{Goal, Level};
+
+/// Most fruits on the board at once outside of the campaign
+pub const DEFAULT_MAX_FRUITS: usize = 5;
+
+/// Stage of the campaign, laid out the same way every time it is played
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stage {
+    pub name: &'static str,
+    pub topology: Topology,
+    pub layout: Layout,
+    pub portals: usize,
+    /// Seed the walls and portals are laid out from
+    pub seed: u64,
+    /// Ticks per second, the snake moves once every tick
+    pub fps: f32,
+    /// Most fruits on the board at once
+    pub fruits: usize,
+    pub goal: Goal,
+}
+
+impl Stage {
+    pub fn arena(&self) -> Result<Arena, ArenaError> {
+        let options = ArenaOptions::new(self.topology, self.layout, self.portals, Some(self.seed))?;
+        Ok(options.generate(&mut thread_rng()))
+    }
+}
+
+pub const STAGES: [Stage; 6] = [
+    Stage {
+        name: "Meadow",
+        topology: Topology::TORUS,
+        layout: Layout::Empty,
+        portals: 0,
+        seed: 1,
+        fps: 10.0,
+        fruits: 5,
+        goal: Goal::Score(5),
+    },
+    Stage {
+        name: "Orchard",
+        topology: Topology::TORUS,
+        layout: Layout::Blocks,
+        portals: 0,
+        seed: 2,
+        fps: 10.0,
+        fruits: 4,
+        goal: Goal::Length(12),
+    },
+    Stage {
+        name: "Rooms",
+        topology: Topology::BOX,
+        layout: Layout::Rooms,
+        portals: 0,
+        seed: 3,
+        fps: 11.0,
+        fruits: 3,
+        goal: Goal::Score(10),
+    },
+    Stage {
+        name: "Wormholes",
+        topology: Topology::CYLINDER,
+        layout: Layout::Blocks,
+        portals: 3,
+        seed: 4,
+        fps: 12.0,
+        fruits: 3,
+        goal: Goal::Length(20),
+    },
+    Stage {
+        name: "Spiral",
+        topology: Topology::BOX,
+        layout: Layout::Spiral,
+        portals: 0,
+        seed: 5,
+        fps: 12.0,
+        fruits: 2,
+        goal: Goal::Score(15),
+    },
+    Stage {
+        name: "Labyrinth",
+        topology: Topology::KLEIN_BOTTLE,
+        layout: Layout::Maze,
+        portals: 2,
+        seed: 6,
+        fps: 13.0,
+        fruits: 2,
+        goal: Goal::Score(20),
+    },
+];
+
+/// Arena and rules of a single game
+#[derive(Debug, Clone)]
+pub struct Rules {
+    pub arena: Arc<Arena>,
+    pub fps: f32,
+    pub max_fruits: usize,
+    /// Stage being played, the game ends once its goal is reached
+    pub level: Option<Level>,
+}
+
+/// Progress through the stages for as long as the server runs. Every player steers the same
+/// snake, so it is shared by everyone playing and lost once the server stops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Campaign {
+    /// Arena of every stage, laid out once as they never change
+    arenas: Vec<Arc<Arena>>,
+    stage: usize,
+    /// Games played on the current stage
+    attempts: u32,
+    completed: u32,
+}
+
+impl Campaign {
+    /// Starts at the first stage, failing if any of the stages cannot be laid out
+    pub fn new() -> Result<Self, ArenaError> {
+        Ok(Self {
+            arenas: STAGES
+                .iter()
+                .map(|stage| stage.arena().map(Arc::new))
+                .collect::<Result<_, _>>()?,
+            stage: 0,
+            attempts: 0,
+            completed: 0,
+        })
+    }
+
+    pub fn stage(&self) -> &'static Stage {
+        &STAGES[self.stage]
+    }
+
+    /// Rules of the next attempt at the current stage
+    pub fn start(&mut self) -> Rules {
+        self.attempts += 1;
+        let stage = self.stage();
+
+        Rules {
+            arena: self.arenas[self.stage].clone(),
+            fps: stage.fps,
+            max_fruits: stage.fruits,
+            level: Some(Level {
+                number: self.stage as u32 + 1,
+                stages: STAGES.len() as u32,
+                name: stage.name.to_string(),
+                goal: stage.goal,
+                attempt: self.attempts,
+                completed: self.completed,
+            }),
+        }
+    }
+
+    /// Moves on to the next stage once the current one is cleared, losing keeps the player on
+    /// it. Clearing the last stage starts the campaign over
+    pub fn finish(&mut self, end: GameEnd) {
+        if end != GameEnd::StageCleared {
+            return;
+        }
+
+        self.attempts = 0;
+        self.stage += 1;
+        if self.stage == STAGES.len() {
+            self.stage = 0;
+            self.completed += 1;
+        }
+    }
+}
+
+/// How the arena and rules of every game are decided
+#[derive(Debug, Clone)]
+pub enum GameMode {
+    /// Endless games at the same speed, in arenas laid out from the options
+    Free(ArenaOptions),
+    /// Stages of the campaign
+    Campaign(Campaign),
+}
+
+impl GameMode {
+    /// Rules of the next game, `fps` is the speed outside of the campaign
+    pub fn next_game(&mut self, fps: f32) -> Rules {
+        match self {
+            Self::Free(options) => Rules {
+                arena: Arc::new(options.generate(&mut thread_rng())),
+                fps,
+                max_fruits: DEFAULT_MAX_FRUITS,
+                level: None,
+            },
+            Self::Campaign(campaign) => campaign.start(),
+        }
+    }
+
+    pub fn finish(&mut self, end: GameEnd) {
+        if let Self::Campaign(campaign) = self {
+            campaign.finish(end);
+        }
+    }
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        Self::Free(ArenaOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_stages_are_laid_out_in_full() {
+        for stage in STAGES {
+            let arena = stage.arena().unwrap();
+
+            assert_eq!(arena.unreachable_cell(), None, "{}", stage.name);
+            assert_eq!(arena.portals().len(), stage.portals, "{}", stage.name);
+            assert_eq!(arena, stage.arena().unwrap(), "{}", stage.name);
+        }
+    }
+
+    #[test]
+    fn test_losing_repeats_stage() {
+        let mut campaign = Campaign::new().unwrap();
+
+        campaign.start();
+        campaign.finish(GameEnd::GameOver);
+        let level = campaign.start().level.unwrap();
+
+        assert_eq!(level.number, 1);
+        assert_eq!(level.attempt, 2);
+    }
+
+    #[test]
+    fn test_clearing_stage_advances_to_next_one() {
+        let mut campaign = Campaign::new().unwrap();
+
+        campaign.start();
+        campaign.finish(GameEnd::StageCleared);
+        let rules = campaign.start();
+
+        assert_eq!(rules.fps, STAGES[1].fps);
+        assert_eq!(rules.max_fruits, STAGES[1].fruits);
+        assert_eq!(
+            rules.level,
+            Some(Level {
+                number: 2,
+                stages: STAGES.len() as u32,
+                name: STAGES[1].name.to_string(),
+                goal: STAGES[1].goal,
+                attempt: 1,
+                completed: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_campaign_starts_over_after_last_stage() {
+        let mut campaign = Campaign::new().unwrap();
+
+        for _ in STAGES {
+            campaign.start();
+            campaign.finish(GameEnd::StageCleared);
+        }
+        let level = campaign.start().level.unwrap();
+
+        assert_eq!(level.number, 1);
+        assert_eq!(level.completed, 1);
+    }
+
+    #[test]
+    fn test_free_games_have_no_level() {
+        let rules = GameMode::default().next_game(7.0);
+
+        assert_eq!(rules.level, None);
+        assert_eq!(rules.fps, 7.0);
+        assert_eq!(rules.max_fruits, DEFAULT_MAX_FRUITS);
+    }
+}
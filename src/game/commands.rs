@@ -1,43 +1,94 @@
+use super::plan::MovePlans;
 use super::Direction;
+use crate::metrics::{self, rejection};
 
 use movement::*;
+use snake_proto::{PlanReceipt, PlannedMove};
 use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
-use tracing::{debug, trace, warn};
+use tokio::sync::mpsc::error::TrySendError;
+use tracing::{debug, trace};
 
 pub mod movement {
+    use super::super::plan::{MAX_PLAN_HORIZON, MAX_PLAN_LENGTH};
     use super::Direction;
 
+    use snake_proto::{PlanReceipt, PlannedMove};
     use std::fmt::Debug;
+    use std::net::IpAddr;
     use thiserror::Error;
     use tokio::sync::mpsc::error::TrySendError;
 
     pub trait OrderMove: Send + Sync + Debug {
         fn issue_move(&self, direction: Direction) -> Result<(), OrderError>;
+
+        /// Schedules moves of `owner` to be made after `tick`, one per tick
+        fn plan_moves(
+            &self,
+            owner: IpAddr,
+            moves: &[PlannedMove],
+            tick: u64,
+        ) -> Result<PlanReceipt, PlanError>;
+
+        /// Drops the moves left in a plan of `owner`
+        fn cancel_plan(&self, owner: IpAddr, id: u64) -> Result<(), PlanError>;
+
+        /// Whether a plan of `owner` votes in `tick`
+        fn has_planned(&self, owner: IpAddr, tick: u64) -> bool;
+
+        /// Whether a move issued right now would be accepted
+        fn is_accepting(&self) -> bool;
     }
 
-    #[derive(Error, Debug)]
+    #[derive(Error, Debug, PartialEq, Eq)]
     pub enum OrderError {
-        #[error("Unable to issue new movement command `{0}`")]
-        IssueMovement(String),
+        #[error("Move command queue is full")]
+        QueueFull,
+
+        #[error("Game is not running, move commands are not accepted")]
+        GameNotRunning,
     }
 
-    impl<T: Debug> From<TrySendError<T>> for OrderError {
+    impl<T> From<TrySendError<T>> for OrderError {
         fn from(send_err: TrySendError<T>) -> Self {
-            Self::IssueMovement(send_err.to_string())
+            match send_err {
+                TrySendError::Full(_) => Self::QueueFull,
+                TrySendError::Closed(_) => Self::GameNotRunning,
+            }
         }
     }
+
+    #[derive(Error, Debug, PartialEq, Eq)]
+    pub enum PlanError {
+        #[error("Plan has no moves")]
+        Empty,
+
+        #[error("Plan has {0} moves, at most {MAX_PLAN_LENGTH} are accepted")]
+        TooLong(usize),
+
+        #[error("Move for tick {tick} comes after a move for tick {previous}, moves have to be in order")]
+        OutOfOrder { tick: u64, previous: u64 },
+
+        #[error("Tick {tick} has already passed, the next one is {next}")]
+        TickPassed { tick: u64, next: u64 },
+
+        #[error("Move for tick {tick} is too far ahead, plans reach at most {MAX_PLAN_HORIZON} ticks ahead, up to tick {last}")]
+        TooFarAhead { tick: u64, last: u64 },
+
+        #[error("There is no plan {0} of this client, it may have been finished already")]
+        NotFound(u64),
+
+        #[error(transparent)]
+        Order(#[from] OrderError),
+    }
 }
 
 #[derive(Debug)]
 pub struct MoveCommandReceiver {
     command_rx: mpsc::Receiver<Direction>,
-}
-
-impl From<mpsc::Receiver<Direction>> for MoveCommandReceiver {
-    fn from(command_rx: mpsc::Receiver<Direction>) -> Self {
-        Self { command_rx }
-    }
+    plans: Arc<Mutex<MovePlans>>,
 }
 
 impl MoveCommandReceiver {
@@ -48,44 +99,134 @@ impl MoveCommandReceiver {
     ) {
         match self.command_rx.recv().await {
             Some(c) => {
-                if c == current_direction.opposite() {
-                    return;
-                }
-                direction_command_counters
-                    .entry(c)
-                    .and_modify(|counter| *counter += 1)
-                    .or_insert(1);
+                metrics::COMMAND_QUEUE_LENGTH.dec();
+                count_vote(c, direction_command_counters, current_direction);
                 debug!("Received a move command from user {:?}", c);
             }
             None => {
-                warn!("Received a move command although it was empty");
+                // No command comes through a closed queue anymore, the game only ticks on
+                debug!("Move command queue is closed");
+                std::future::pending::<()>().await
             }
         }
     }
+
+    /// Counts the moves planned for `tick` like commands received during it
+    pub fn take_planned(
+        &self,
+        tick: u64,
+        direction_command_counters: &mut HashMap<Direction, u32>,
+        current_direction: &Direction,
+    ) {
+        for direction in self.plans.lock().unwrap().take_due(tick) {
+            count_vote(direction, direction_command_counters, current_direction);
+        }
+    }
+}
+
+fn count_vote(
+    direction: Direction,
+    direction_command_counters: &mut HashMap<Direction, u32>,
+    current_direction: &Direction,
+) {
+    if direction == current_direction.opposite() {
+        metrics::COMMANDS_REJECTED
+            .with_label_values(&[rejection::OPPOSITE_DIRECTION])
+            .inc();
+        return;
+    }
+    direction_command_counters
+        .entry(direction)
+        .and_modify(|counter| *counter += 1)
+        .or_insert(1);
 }
 
 #[derive(Debug, Clone)]
 pub struct MoveCommandIssuer {
     command_sender: mpsc::Sender<Direction>,
+    plans: Arc<Mutex<MovePlans>>,
 }
 
 impl MoveCommandIssuer {
     pub fn set_issuer(&mut self, issuer: mpsc::Sender<Direction>) {
+        // Commands left in the previous queue are dropped along with it, and so are plans
+        reset_queue_metrics(&issuer);
         self.command_sender = issuer;
+        self.plans.lock().unwrap().clear();
+    }
+
+    /// Receiving end of the current command queue, which shares plans with this issuer
+    pub fn receiver(&self, command_rx: mpsc::Receiver<Direction>) -> MoveCommandReceiver {
+        MoveCommandReceiver {
+            command_rx,
+            plans: Arc::clone(&self.plans),
+        }
     }
+
+    /// Rejects every further move as if the game was not running, used when shutting down
+    pub fn close(&mut self) {
+        let (closed_sender, _) = mpsc::channel(1);
+        self.set_issuer(closed_sender);
+    }
+}
+
+fn reset_queue_metrics(command_sender: &mpsc::Sender<Direction>) {
+    metrics::COMMAND_QUEUE_LENGTH.set(0);
+    metrics::COMMAND_QUEUE_CAPACITY.set(command_sender.max_capacity() as i64);
 }
 
 impl From<mpsc::Sender<Direction>> for MoveCommandIssuer {
     fn from(command_sender: mpsc::Sender<Direction>) -> Self {
-        Self { command_sender }
+        reset_queue_metrics(&command_sender);
+        Self {
+            command_sender,
+            plans: Arc::default(),
+        }
     }
 }
 
 impl OrderMove for MoveCommandIssuer {
     fn issue_move(&self, direction: Direction) -> Result<(), OrderError> {
         trace!("Issueing new move: {:?}", direction);
-        self.command_sender.try_send(direction)?;
+        if let Err(err) = self.command_sender.try_send(direction) {
+            let reason = match err {
+                TrySendError::Full(_) => rejection::QUEUE_FULL,
+                TrySendError::Closed(_) => rejection::QUEUE_CLOSED,
+            };
+            metrics::COMMANDS_REJECTED
+                .with_label_values(&[reason])
+                .inc();
+            return Err(err.into());
+        }
+
+        metrics::COMMANDS_RECEIVED.inc();
+        metrics::COMMAND_QUEUE_LENGTH.inc();
 
         Ok(())
     }
+
+    fn plan_moves(
+        &self,
+        owner: IpAddr,
+        moves: &[PlannedMove],
+        tick: u64,
+    ) -> Result<PlanReceipt, PlanError> {
+        if self.command_sender.is_closed() {
+            return Err(OrderError::GameNotRunning.into());
+        }
+
+        self.plans.lock().unwrap().add(owner, moves, tick)
+    }
+
+    fn cancel_plan(&self, owner: IpAddr, id: u64) -> Result<(), PlanError> {
+        self.plans.lock().unwrap().cancel(owner, id)
+    }
+
+    fn has_planned(&self, owner: IpAddr, tick: u64) -> bool {
+        self.plans.lock().unwrap().has_move(owner, tick)
+    }
+
+    fn is_accepting(&self) -> bool {
+        !self.command_sender.is_closed() && self.command_sender.capacity() > 0
+    }
 }
@@ -1,3 +1,4 @@
-pub const BOARD_SIZE_Y: u16 = 20;
-pub const BOARD_SIZE_X: u16 = 40;
+pub use snake_proto::{BOARD_SIZE_X, BOARD_SIZE_Y, MAX_PORTAL_PAIRS};
 pub const START_DELAY_IN_SECS: u64 = 3;
+/// Cells the snake takes up at the start, going down from the center of the board
+pub const SPAWN_LENGTH: u16 = 3;
@@ -3,7 +3,9 @@ use rand::{seq::SliceRandom, thread_rng};
 
 use super::point::Point;
 
-#[derive(Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Fruit {
     pub point: Point,
 }
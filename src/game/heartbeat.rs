@@ -0,0 +1,168 @@
+use super::consts::START_DELAY_IN_SECS;
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Ticks which may be missed in a row before the game loop is considered stuck
+const TOLERATED_MISSED_TICKS: u32 = 10;
+const MIN_TOLERANCE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Counting down before the first move of a game
+    Starting,
+    Running,
+    /// Game loop has finished and will not tick anymore
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Liveness {
+    Alive,
+    Stopped,
+    Stalled { silent_for: Duration },
+}
+
+/// Progress of the game loop, updated by the game and inspected by health checks
+#[derive(Debug)]
+pub struct Heartbeat {
+    phase: Phase,
+    phase_since: Instant,
+    last_tick: Option<Instant>,
+    tolerance: Duration,
+}
+
+impl Heartbeat {
+    pub fn new(fps: f32) -> Self {
+        Self {
+            phase: Phase::Starting,
+            phase_since: Instant::now(),
+            last_tick: None,
+            tolerance: tolerance(fps),
+        }
+    }
+
+    /// Follows the speed of a new game, which may differ from the previous one in the campaign
+    pub fn set_fps(&mut self, fps: f32) {
+        self.tolerance = tolerance(fps);
+    }
+
+    pub fn set_phase(&mut self, phase: Phase) {
+        self.set_phase_at(phase, Instant::now());
+    }
+
+    fn set_phase_at(&mut self, phase: Phase, now: Instant) {
+        self.phase = phase;
+        self.phase_since = now;
+    }
+
+    pub fn tick(&mut self) {
+        self.tick_at(Instant::now());
+    }
+
+    fn tick_at(&mut self, now: Instant) {
+        if self.phase != Phase::Running {
+            self.set_phase_at(Phase::Running, now);
+        }
+        self.last_tick = Some(now);
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn last_tick(&self) -> Option<Instant> {
+        self.last_tick
+    }
+
+    pub fn liveness(&self) -> Liveness {
+        self.liveness_at(Instant::now())
+    }
+
+    fn liveness_at(&self, now: Instant) -> Liveness {
+        let silent_for = match self.phase {
+            Phase::Stopped => return Liveness::Stopped,
+            Phase::Running => {
+                now.saturating_duration_since(self.last_tick.unwrap_or(self.phase_since))
+            }
+            // The countdown before the first move is not silence
+            Phase::Starting => now
+                .saturating_duration_since(self.phase_since)
+                .saturating_sub(Duration::from_secs(START_DELAY_IN_SECS)),
+        };
+
+        if silent_for > self.tolerance {
+            Liveness::Stalled { silent_for }
+        } else {
+            Liveness::Alive
+        }
+    }
+}
+
+fn tolerance(fps: f32) -> Duration {
+    Duration::from_secs_f32(TOLERATED_MISSED_TICKS as f32 / fps).max(MIN_TOLERANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_countdown_is_alive() {
+        let heartbeat = Heartbeat::new(10.0);
+        let now = heartbeat.phase_since + Duration::from_secs(START_DELAY_IN_SECS);
+
+        assert_eq!(heartbeat.liveness_at(now), Liveness::Alive);
+    }
+
+    #[test]
+    fn test_first_tick_switches_to_running() {
+        let mut heartbeat = Heartbeat::new(10.0);
+        heartbeat.tick();
+
+        assert_eq!(heartbeat.phase(), Phase::Running);
+        assert!(heartbeat.last_tick().is_some());
+    }
+
+    #[test]
+    fn test_missing_ticks_stall() {
+        let mut heartbeat = Heartbeat::new(10.0);
+        let started = Instant::now();
+        heartbeat.tick_at(started);
+
+        assert_eq!(
+            heartbeat.liveness_at(started + Duration::from_millis(500)),
+            Liveness::Alive
+        );
+        assert_eq!(
+            heartbeat.liveness_at(started + Duration::from_secs(2)),
+            Liveness::Stalled {
+                silent_for: Duration::from_secs(2)
+            }
+        );
+    }
+
+    #[test]
+    fn test_tolerance_follows_speed() {
+        let mut heartbeat = Heartbeat::new(10.0);
+        heartbeat.set_fps(2.0);
+        let started = Instant::now();
+        heartbeat.tick_at(started);
+
+        assert_eq!(
+            heartbeat.liveness_at(started + Duration::from_secs(2)),
+            Liveness::Alive
+        );
+    }
+
+    #[test]
+    fn test_stopped_loop() {
+        let mut heartbeat = Heartbeat::new(10.0);
+        heartbeat.set_phase(Phase::Stopped);
+
+        assert_eq!(heartbeat.liveness(), Liveness::Stopped);
+    }
+}
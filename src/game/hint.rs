@@ -0,0 +1,184 @@
+use super::analysis::{bfs, region_size, Grid, DIRECTIONS};
+use super::arena::Arena;
+use super::point::{Direction, Point};
+
+pub use snake_proto::Hint;
+
+/// Hint for the snake with `body` (head first) moving in `direction` in `arena`
+pub fn hint_for(
+    tick: u64,
+    body: &[Point],
+    direction: Direction,
+    fruits: &[Point],
+    arena: &Arena,
+) -> Hint {
+    let mut hint = Hint {
+        tick,
+        safe: vec![],
+        dead_ends: vec![],
+        path_to_fruit: None,
+    };
+    let Some(&head) = body.first() else {
+        return hint;
+    };
+
+    // The tail only moves away when the snake has not just eaten, which cannot be told from
+    // the outside, so it is counted as an obstacle
+    let mut obstacles = Grid::new(arena);
+    obstacles.extend(body);
+    for candidate in DIRECTIONS
+        .into_iter()
+        .filter(|&candidate| candidate != direction.opposite())
+    {
+        let Some(next) = obstacles.neighbour(head, candidate) else {
+            continue;
+        };
+        if obstacles.is_blocked(&next) {
+            continue;
+        }
+        hint.safe.push(candidate);
+
+        // After the move the new head leads the body and the last segment is gone
+        let mut moved = Grid::new(arena);
+        moved.extend(std::iter::once(&next).chain(&body[..body.len() - 1]));
+        if region_size(next, &moved) < body.len() {
+            hint.dead_ends.push(candidate);
+        }
+    }
+    // Reversing is never taken into account, as it would lead into the neck
+    hint.path_to_fruit = bfs(head, &obstacles, |point| fruits.contains(point));
+
+    hint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::consts::*;
+    use crate::game::point::Topology;
+    use pretty_assertions::assert_eq;
+
+    fn vertical_snake(x: u16, length: u16) -> Vec<Point> {
+        (0..length).map(|y| Point::new(5 + y, x)).collect()
+    }
+
+    #[test]
+    fn test_every_direction_but_reverse_is_safe_on_empty_board() {
+        let hint = hint_for(
+            1,
+            &vertical_snake(5, 3),
+            Direction::Up,
+            &[],
+            &Arena::new(Topology::TORUS),
+        );
+
+        assert_eq!(
+            hint.safe,
+            vec![Direction::Up, Direction::Left, Direction::Right]
+        );
+        assert_eq!(hint.dead_ends, vec![]);
+        assert_eq!(hint.path_to_fruit, None);
+    }
+
+    #[test]
+    fn test_moves_into_body_are_not_safe() {
+        // Head at (5, 5) moving left, with the body curling above and to the left of it
+        let body = vec![
+            Point::new(5, 5),
+            Point::new(5, 6),
+            Point::new(4, 6),
+            Point::new(4, 5),
+            Point::new(4, 4),
+            Point::new(5, 4),
+        ];
+
+        let hint = hint_for(1, &body, Direction::Left, &[], &Arena::new(Topology::TORUS));
+
+        assert_eq!(hint.safe, vec![Direction::Down]);
+    }
+
+    #[test]
+    fn test_pocket_smaller_than_snake_is_dead_end() {
+        // Two columns of snake through the whole board, joined at the top by the head
+        let mut body: Vec<Point> = (1..BOARD_SIZE_Y).map(|y| Point::new(y, 1)).collect();
+        body.extend((0..BOARD_SIZE_Y).rev().map(|y| Point::new(y, 3)));
+        body.insert(0, Point::new(0, 2));
+        body.insert(1, Point::new(0, 1));
+
+        let hint = hint_for(
+            1,
+            &body,
+            Direction::Right,
+            &[],
+            &Arena::new(Topology::TORUS),
+        );
+
+        // Both up and down lead into the column between the snake's walls, which is closed off
+        // at the top by the head itself
+        assert_eq!(hint.safe, vec![Direction::Up, Direction::Down]);
+        assert_eq!(hint.dead_ends, vec![Direction::Up, Direction::Down]);
+    }
+
+    #[test]
+    fn test_path_to_nearest_fruit() {
+        let fruits = [Point::new(1, 5), Point::new(5, 8)];
+
+        let hint = hint_for(
+            1,
+            &vertical_snake(5, 3),
+            Direction::Up,
+            &fruits,
+            &Arena::new(Topology::TORUS),
+        );
+
+        assert_eq!(
+            hint.path_to_fruit,
+            Some(vec![Direction::Right, Direction::Right, Direction::Right])
+        );
+    }
+
+    #[test]
+    fn test_path_to_fruit_wraps_around_walls() {
+        let fruits = [Point::new(BOARD_SIZE_Y - 1, 5)];
+
+        let hint = hint_for(
+            1,
+            &vertical_snake(5, 3),
+            Direction::Up,
+            &fruits,
+            &Arena::new(Topology::TORUS),
+        );
+
+        // Going up through the top wall is shorter than around the body
+        assert_eq!(hint.path_to_fruit.map(|path| path.len()), Some(6));
+    }
+
+    #[test]
+    fn test_no_hint_without_snake() {
+        let hint = hint_for(
+            0,
+            &[],
+            Direction::Up,
+            &[Point::new(1, 1)],
+            &Arena::default(),
+        );
+
+        assert_eq!(hint.safe, vec![]);
+        assert_eq!(hint.path_to_fruit, None);
+    }
+
+    #[test]
+    fn test_edges_of_box_are_not_safe() {
+        let hint = hint_for(
+            1,
+            &vertical_snake(0, 3),
+            Direction::Up,
+            &[Point::new(BOARD_SIZE_Y - 1, 0)],
+            &Arena::new(Topology::BOX),
+        );
+
+        assert_eq!(hint.safe, vec![Direction::Up, Direction::Right]);
+        // Around the body instead of through the top wall
+        assert_eq!(hint.path_to_fruit.map(|path| path.len()), Some(16));
+    }
+}
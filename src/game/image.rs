@@ -0,0 +1,387 @@
+use super::board::{Board, CellSymbol};
+
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::time::Duration;
+use thiserror::Error;
+
+pub const DEFAULT_CELL_SIZE: u32 = 16;
+pub const MAX_CELL_SIZE: u32 = 64;
+/// GIF frame delays are stored in hundredths of a second, most viewers ignore anything below 2
+const MIN_GIF_FRAME_DELAY_CS: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub background: Rgb,
+    pub wall: Rgb,
+    pub snake: Rgb,
+    pub snake_head: Rgb,
+    pub fruit: Rgb,
+    pub portal: Rgb,
+}
+
+impl Palette {
+    /// Greenish LCD look of the old Nokia phones
+    pub const CLASSIC: Palette = Palette {
+        background: Rgb(0xc7, 0xf0, 0xd8),
+        wall: Rgb(0x43, 0x52, 0x3d),
+        snake: Rgb(0x43, 0x52, 0x3d),
+        snake_head: Rgb(0x1e, 0x26, 0x1b),
+        fruit: Rgb(0x6b, 0x7f, 0x5f),
+        portal: Rgb(0x86, 0x9a, 0x78),
+    };
+
+    pub const DARK: Palette = Palette {
+        background: Rgb(0x1e, 0x1e, 0x2e),
+        wall: Rgb(0x58, 0x5b, 0x70),
+        snake: Rgb(0xa6, 0xe3, 0xa1),
+        snake_head: Rgb(0x40, 0xa0, 0x2b),
+        fruit: Rgb(0xf3, 0x8b, 0xa8),
+        portal: Rgb(0xcb, 0xa6, 0xf7),
+    };
+
+    pub const LIGHT: Palette = Palette {
+        background: Rgb(0xff, 0xff, 0xff),
+        wall: Rgb(0x33, 0x33, 0x33),
+        snake: Rgb(0x4c, 0xaf, 0x50),
+        snake_head: Rgb(0x1b, 0x5e, 0x20),
+        fruit: Rgb(0xe5, 0x39, 0x35),
+        portal: Rgb(0x8e, 0x24, 0xaa),
+    };
+
+    pub fn colour_of(&self, cell: CellSymbol) -> Rgb {
+        self.colours()[Self::index_of(cell) as usize]
+    }
+
+    fn index_of(cell: CellSymbol) -> u8 {
+        match cell {
+            CellSymbol::Board => 0,
+            CellSymbol::Wall(_) | CellSymbol::Junction(_) => 1,
+            CellSymbol::Snake => 2,
+            CellSymbol::SnakeHead => 3,
+            CellSymbol::Fruit => 4,
+            CellSymbol::Portal(_) => 5,
+        }
+    }
+
+    /// Colours ordered the way `index_of` refers to them
+    fn colours(&self) -> [Rgb; 6] {
+        [
+            self.background,
+            self.wall,
+            self.snake,
+            self.snake_head,
+            self.fruit,
+            self.portal,
+        ]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+impl TryFrom<String> for Palette {
+    type Error = ImageError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "classic" => Ok(Self::CLASSIC),
+            "dark" => Ok(Self::DARK),
+            "light" => Ok(Self::LIGHT),
+            _ => Err(ImageError::UnknownPalette(value)),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ImageError {
+    #[error("There are 3 possible palettes: ['classic', 'dark', 'light']. `{0}` does not match any of them")]
+    UnknownPalette(String),
+
+    #[error("Cell size has to be between 1 and {MAX_CELL_SIZE} pixels, got `{0}`")]
+    InvalidCellSize(u32),
+
+    #[error("Encoding PNG failed: {0}")]
+    PngEncoding(#[from] png::EncodingError),
+
+    #[error("Writing SVG failed: {0}")]
+    SvgWriting(#[from] std::fmt::Error),
+
+    #[error("Encoding GIF failed: {0}")]
+    GifEncoding(#[from] gif::EncodingError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageOptions {
+    cell_size: u32,
+    palette: Palette,
+}
+
+impl ImageOptions {
+    pub fn new(cell_size: u32, palette: Palette) -> Result<Self, ImageError> {
+        if cell_size == 0 || cell_size > MAX_CELL_SIZE {
+            return Err(ImageError::InvalidCellSize(cell_size));
+        }
+
+        Ok(Self { cell_size, palette })
+    }
+
+    pub fn cell_size(&self) -> u32 {
+        self.cell_size
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Same options with cells of at most `max` pixels
+    pub fn with_cell_size_at_most(self, max: u32) -> Self {
+        Self {
+            cell_size: self.cell_size.min(max.max(1)),
+            ..self
+        }
+    }
+
+    /// Width and height in pixels of a board rendered with these options
+    pub fn dimensions(&self) -> (u32, u32) {
+        let (rows, columns) = Board::canvas_size();
+        (
+            columns as u32 * self.cell_size,
+            rows as u32 * self.cell_size,
+        )
+    }
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: DEFAULT_CELL_SIZE,
+            palette: Palette::default(),
+        }
+    }
+}
+
+pub fn render_svg(
+    board: &Board,
+    options: &ImageOptions,
+    wr: &mut impl Write,
+) -> Result<(), ImageError> {
+    let (width, height) = options.dimensions();
+    let size = options.cell_size;
+
+    write!(
+        wr,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" shape-rendering="crispEdges">"#
+    )?;
+    write!(
+        wr,
+        r#"<rect width="{width}" height="{height}" fill="{}"/>"#,
+        options.palette.background.hex()
+    )?;
+
+    for (y, row) in board.rows().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if let CellSymbol::Board = cell {
+                continue;
+            }
+
+            let (px, py) = (x as u32 * size, y as u32 * size);
+            let fill = options.palette.colour_of(*cell).hex();
+            match cell {
+                CellSymbol::Fruit => {
+                    let radius = size as f32 / 2.0;
+                    write!(
+                        wr,
+                        r#"<circle cx="{}" cy="{}" r="{}" fill="{fill}"/>"#,
+                        px as f32 + radius,
+                        py as f32 + radius,
+                        radius * 0.8
+                    )?
+                }
+                _ => write!(
+                    wr,
+                    r#"<rect x="{px}" y="{py}" width="{size}" height="{size}" fill="{fill}"/>"#
+                )?,
+            }
+        }
+    }
+
+    wr.write_str("</svg>")?;
+
+    Ok(())
+}
+
+/// Rasterizes the board into one byte per pixel, each being an index into `Palette::colours`
+fn rasterize_indexed(board: &Board, options: &ImageOptions) -> Vec<u8> {
+    let (width, height) = options.dimensions();
+    let size = options.cell_size as usize;
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+
+    for row in board.rows() {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|cell| std::iter::repeat_n(Palette::index_of(*cell), size))
+            .collect();
+        for _ in 0..size {
+            pixels.extend_from_slice(&line);
+        }
+    }
+
+    pixels
+}
+
+/// Rasterizes the board into tightly packed RGB8 pixels, row by row
+pub fn rasterize(board: &Board, options: &ImageOptions) -> Vec<u8> {
+    let colours = options.palette.colours();
+
+    rasterize_indexed(board, options)
+        .into_iter()
+        .flat_map(|index| {
+            let Rgb(r, g, b) = colours[index as usize];
+            [r, g, b]
+        })
+        .collect()
+}
+
+pub fn render_png(board: &Board, options: &ImageOptions) -> Result<Vec<u8>, ImageError> {
+    let (width, height) = options.dimensions();
+    let mut out = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&rasterize(board, options))?;
+    writer.finish()?;
+
+    Ok(out)
+}
+
+/// Encodes consecutive boards as a looping animated GIF, each frame is shown for its paired duration
+pub fn render_gif<'a>(
+    frames: impl IntoIterator<Item = (&'a Board, Duration)>,
+    options: &ImageOptions,
+) -> Result<Vec<u8>, ImageError> {
+    let (width, height) = options.dimensions();
+    let global_palette: Vec<u8> = options
+        .palette
+        .colours()
+        .iter()
+        .flat_map(|Rgb(r, g, b)| [*r, *g, *b])
+        .collect();
+    let mut out = Vec::new();
+
+    {
+        let mut encoder =
+            gif::Encoder::new(&mut out, width as u16, height as u16, &global_palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        for (board, shown_for) in frames {
+            let delay = (shown_for.as_millis() / 10).min(u16::MAX as u128) as u16;
+            let frame = gif::Frame {
+                width: width as u16,
+                height: height as u16,
+                delay: delay.max(MIN_GIF_FRAME_DELAY_CS),
+                buffer: Cow::Owned(rasterize_indexed(board, options)),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame)?;
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::point::Point;
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_rasterize_size_and_wall_colour() {
+        let options = ImageOptions::new(2, Palette::LIGHT).unwrap();
+        let pixels = rasterize(&Board::default(), &options);
+        let (width, height) = options.dimensions();
+
+        assert_eq!(pixels.len(), (width * height * 3) as usize);
+        assert_eq!(&pixels[..3], &[0x33, 0x33, 0x33]);
+    }
+
+    #[test]
+    fn test_rasterize_draws_snake_head() {
+        let mut board = Board::default();
+        board.change_cell_symbol(&Point::new(0, 0), CellSymbol::SnakeHead);
+        let options = ImageOptions::new(1, Palette::DARK).unwrap();
+        let (width, _) = options.dimensions();
+
+        let pixels = rasterize(&board, &options);
+        // Point (0, 0) lays at canvas (1, 1), just inside the walls
+        let offset = (width as usize + 1) * 3;
+
+        assert_eq!(&pixels[offset..offset + 3], &[0x40, 0xa0, 0x2b]);
+    }
+
+    #[test]
+    fn test_png_has_signature() {
+        let png = render_png(&Board::default(), &ImageOptions::default()).unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_svg_contains_fruit() {
+        let mut board = Board::default();
+        board.change_cell_symbol(&Point::new(3, 3), CellSymbol::Fruit);
+        let mut svg = String::new();
+
+        render_svg(&board, &ImageOptions::default(), &mut svg).unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 1);
+    }
+
+    #[test]
+    fn test_gif_has_signature_and_trailer() {
+        let boards = [Board::default(), Board::default()];
+        let frames = boards.iter().map(|b| (b, Duration::from_millis(100)));
+
+        let gif = render_gif(frames, &ImageOptions::default()).unwrap();
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif.last(), Some(&0x3b));
+    }
+
+    #[test]
+    fn test_cell_size_is_clamped() {
+        let options = ImageOptions::new(MAX_CELL_SIZE, Palette::default()).unwrap();
+
+        assert_eq!(options.with_cell_size_at_most(8).cell_size(), 8);
+        assert_eq!(options.with_cell_size_at_most(0).cell_size(), 1);
+        assert_eq!(
+            ImageOptions::default()
+                .with_cell_size_at_most(MAX_CELL_SIZE)
+                .cell_size(),
+            DEFAULT_CELL_SIZE
+        );
+    }
+
+    #[test]
+    fn test_cell_size_out_of_range() {
+        assert!(ImageOptions::new(0, Palette::default()).is_err());
+        assert!(ImageOptions::new(MAX_CELL_SIZE + 1, Palette::default()).is_err());
+    }
+}
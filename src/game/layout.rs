@@ -0,0 +1,340 @@
+//! Interior walls laid out from a seed, sized to the board and never cutting off any free cell
+
+use super::arena::{Arena, ArenaError};
+use super::consts::*;
+use super::point::Point;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Width of the openings left in walls between two areas
+const DOORWAY: u16 = 2;
+/// Rooms across and down the board
+const ROOMS: (u16, u16) = (4, 2);
+/// Free cells for every block scattered around
+const CELLS_PER_BLOCK: usize = 40;
+/// Distance between the rings of a spiral, which leaves corridors one cell narrower
+const RING_SPACING: u16 = 3;
+/// Chance of knocking down a wall left between two passages of a maze, so it gets loops
+const MAZE_LOOP_CHANCE: f64 = 0.25;
+
+/// How walls are put up inside the border of the board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// No walls, only the border
+    #[default]
+    Empty,
+    /// Rooms connected through doorways
+    Rooms,
+    /// Small blocks scattered around
+    Blocks,
+    /// Rings around the center, each open on the side next to the previous one
+    Spiral,
+    /// Narrow passages, with a few loops so the snake is not always trapped
+    Maze,
+}
+
+impl Layout {
+    const NAMED: [(&'static str, Self); 5] = [
+        ("empty", Self::Empty),
+        ("rooms", Self::Rooms),
+        ("blocks", Self::Blocks),
+        ("spiral", Self::Spiral),
+        ("maze", Self::Maze),
+    ];
+
+    /// Puts up the walls of this layout in `arena`, leaving out the ones ahead of the snake
+    pub fn lay_out(self, arena: &mut Arena, rng: &mut impl Rng) -> Result<(), ArenaError> {
+        let walls = match self {
+            Self::Empty => return Ok(()),
+            Self::Blocks => {
+                scatter_blocks(arena, rng);
+                return Ok(());
+            }
+            Self::Rooms => rooms(rng),
+            Self::Spiral => spiral(rng),
+            Self::Maze => maze(rng),
+        };
+
+        arena.add_walls(
+            walls
+                .into_iter()
+                .filter(|point| !Arena::is_spawn_zone(point)),
+        )
+    }
+}
+
+/// Ranges of cells between `dividers`, which split `0..size`
+fn segments(dividers: &[u16], size: u16) -> Vec<(u16, u16)> {
+    let starts = std::iter::once(0).chain(dividers.iter().map(|divider| divider + 1));
+    let ends = dividers.iter().copied().chain(std::iter::once(size));
+
+    starts.zip(ends).collect()
+}
+
+/// Start of a doorway somewhere along `from..to`
+fn doorway(from: u16, to: u16, rng: &mut impl Rng) -> u16 {
+    rng.gen_range(from..=to - DOORWAY)
+}
+
+/// Walls between a grid of rooms, with a doorway into every neighbouring room
+fn rooms(rng: &mut impl Rng) -> Vec<Point> {
+    let dividers_x: Vec<u16> = (1..ROOMS.0).map(|i| i * BOARD_SIZE_X / ROOMS.0).collect();
+    let dividers_y: Vec<u16> = (1..ROOMS.1).map(|i| i * BOARD_SIZE_Y / ROOMS.1).collect();
+    let mut walls = vec![];
+
+    for &y in &dividers_y {
+        for (from, to) in segments(&dividers_x, BOARD_SIZE_X) {
+            let door = doorway(from, to, rng);
+            walls.extend(
+                (from..to)
+                    .filter(|x| !(door..door + DOORWAY).contains(x))
+                    .map(|x| Point::new(y, x)),
+            );
+        }
+        // Where the walls cross
+        walls.extend(dividers_x.iter().map(|&x| Point::new(y, x)));
+    }
+    for &x in &dividers_x {
+        for (from, to) in segments(&dividers_y, BOARD_SIZE_Y) {
+            let door = doorway(from, to, rng);
+            walls.extend(
+                (from..to)
+                    .filter(|y| !(door..door + DOORWAY).contains(y))
+                    .map(|y| Point::new(y, x)),
+            );
+        }
+    }
+
+    walls
+}
+
+/// Blocks of up to 2 by 3 cells, those which would cut off a part of the board are left out
+fn scatter_blocks(arena: &mut Arena, rng: &mut impl Rng) {
+    let blocks = BOARD_SIZE_X as usize * BOARD_SIZE_Y as usize / CELLS_PER_BLOCK;
+
+    for _ in 0..blocks {
+        let (height, width) = match rng.gen() {
+            true => (rng.gen_range(1..=2), rng.gen_range(1..=3)),
+            false => (rng.gen_range(1..=3), rng.gen_range(1..=2)),
+        };
+        let y = rng.gen_range(0..=BOARD_SIZE_Y - height);
+        let x = rng.gen_range(0..=BOARD_SIZE_X - width);
+
+        let block: Vec<Point> = (y..y + height)
+            .flat_map(|y| (x..x + width).map(move |x| Point::new(y, x)))
+            .collect();
+        // Overlapping an earlier block, or too close to the snake
+        let _ = arena.add_walls(block);
+    }
+}
+
+/// Rectangular rings around the center, each with a doorway a quarter turn further than the
+/// one outside of it
+fn spiral(rng: &mut impl Rng) -> Vec<Point> {
+    let mut walls = vec![];
+    let mut side = rng.gen_range(0..4);
+
+    for inset in (DOORWAY..)
+        .step_by(RING_SPACING as usize)
+        .take_while(|inset| BOARD_SIZE_Y - 1 - inset > inset + DOORWAY)
+    {
+        let (top, bottom) = (inset, BOARD_SIZE_Y - 1 - inset);
+        let (left, right) = (inset, BOARD_SIZE_X - 1 - inset);
+
+        // Sides go clockwise from the top, doorways keep off the corners
+        let door: Vec<Point> = match side {
+            0 | 2 => {
+                let y = if side == 0 { top } else { bottom };
+                let x = doorway(left + 1, right, rng);
+                (x..x + DOORWAY).map(|x| Point::new(y, x)).collect()
+            }
+            _ => {
+                let x = if side == 1 { right } else { left };
+                let y = doorway(top + 1, bottom, rng);
+                (y..y + DOORWAY).map(|y| Point::new(y, x)).collect()
+            }
+        };
+
+        let ring = (left..=right)
+            .flat_map(|x| [Point::new(top, x), Point::new(bottom, x)])
+            .chain((top + 1..bottom).flat_map(|y| [Point::new(y, left), Point::new(y, right)]));
+        walls.extend(ring.filter(|point| !door.contains(point)));
+
+        side = (side + 1) % 4;
+    }
+
+    walls
+}
+
+/// Passages one cell wide carved out by a random walk, the border of the board included
+fn maze(rng: &mut impl Rng) -> Vec<Point> {
+    // Passages run through every odd row and column, walls are left between them
+    let (rows, columns) = (BOARD_SIZE_Y / 2, BOARD_SIZE_X / 2);
+    let cell = |row: u16, column: u16| Point::new(2 * row + 1, 2 * column + 1);
+    let mut open = vec![vec![false; BOARD_SIZE_X as usize]; BOARD_SIZE_Y as usize];
+    let mut carve = |point: Point| open[point.y as usize][point.x as usize] = true;
+    let mut visited = vec![vec![false; columns as usize]; rows as usize];
+
+    // Depth first, so it is a single tree of passages
+    let mut stack = vec![(rows / 2, columns / 2)];
+    visited[(rows / 2) as usize][(columns / 2) as usize] = true;
+    carve(cell(rows / 2, columns / 2));
+    while let Some(&(row, column)) = stack.last() {
+        let unvisited: Vec<(u16, u16)> = [
+            (row.wrapping_sub(1), column),
+            (row + 1, column),
+            (row, column.wrapping_sub(1)),
+            (row, column + 1),
+        ]
+        .into_iter()
+        .filter(|&(r, c)| r < rows && c < columns && !visited[r as usize][c as usize])
+        .collect();
+
+        let Some(&(next_row, next_column)) = unvisited.choose(rng) else {
+            stack.pop();
+            continue;
+        };
+        visited[next_row as usize][next_column as usize] = true;
+        let (from, to) = (cell(row, column), cell(next_row, next_column));
+        carve(Point::new((from.y + to.y) / 2, (from.x + to.x) / 2));
+        carve(to);
+        stack.push((next_row, next_column));
+    }
+
+    // Knocking down some of the walls between neighbouring passages adds loops
+    for row in 0..rows {
+        for column in 0..columns {
+            let from = cell(row, column);
+            for (next_row, next_column) in [(row + 1, column), (row, column + 1)] {
+                if next_row < rows && next_column < columns && rng.gen_bool(MAZE_LOOP_CHANCE) {
+                    let to = cell(next_row, next_column);
+                    carve(Point::new((from.y + to.y) / 2, (from.x + to.x) / 2));
+                }
+            }
+        }
+    }
+
+    (0..BOARD_SIZE_Y)
+        .flat_map(|y| (0..BOARD_SIZE_X).map(move |x| Point::new(y, x)))
+        .filter(|point| !open[point.y as usize][point.x as usize])
+        .collect()
+}
+
+#[derive(Debug, Error)]
+pub enum LayoutError {
+    #[error("There are 5 possible layouts: ['empty', 'rooms', 'blocks', 'spiral', 'maze']. `{0}` does not match any of them")]
+    ConversionFromStringError(String),
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = Self::NAMED
+            .iter()
+            .find(|(_, layout)| layout == self)
+            .expect("every layout is named");
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Layout {
+    type Err = LayoutError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let lowercase = value.trim().to_lowercase();
+        Self::NAMED
+            .iter()
+            .find(|(name, _)| *name == lowercase)
+            .map(|(_, layout)| *layout)
+            .ok_or_else(|| LayoutError::ConversionFromStringError(value.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::get_center_of_board_coordinates;
+    use crate::game::point::Topology;
+    use pretty_assertions::assert_eq;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const TOPOLOGIES: [Topology; 3] = [Topology::TORUS, Topology::BOX, Topology::KLEIN_BOTTLE];
+
+    fn laid_out(layout: Layout, topology: Topology, seed: u64) -> Arena {
+        let mut arena = Arena::new(topology);
+        layout
+            .lay_out(&mut arena, &mut StdRng::seed_from_u64(seed))
+            .unwrap_or_else(|err| panic!("{layout} on {topology} of seed {seed}: {err}"));
+
+        arena
+    }
+
+    #[test]
+    fn test_every_free_cell_is_reachable() {
+        for (_, layout) in Layout::NAMED {
+            for topology in TOPOLOGIES {
+                for seed in 0..8 {
+                    let arena = laid_out(layout, topology, seed);
+
+                    assert_eq!(arena.unreachable_cell(), None, "{layout} on {topology}");
+                    assert_eq!(
+                        arena.walls().next().is_some(),
+                        layout != Layout::Empty,
+                        "{layout}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_snake_starts_clear_of_walls() {
+        let center = get_center_of_board_coordinates();
+
+        for (_, layout) in Layout::NAMED {
+            for seed in 0..8 {
+                let arena = laid_out(layout, Topology::TORUS, seed);
+
+                for y in center.y - 3..center.y + 3 {
+                    assert!(!arena.is_wall(&Point::new(y, center.x)), "{layout}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_lays_out_same_walls() {
+        for (_, layout) in Layout::NAMED {
+            assert_eq!(
+                laid_out(layout, Topology::TORUS, 42),
+                laid_out(layout, Topology::TORUS, 42)
+            );
+        }
+        assert_ne!(
+            laid_out(Layout::Maze, Topology::TORUS, 1),
+            laid_out(Layout::Maze, Topology::TORUS, 2)
+        );
+    }
+
+    #[test]
+    fn test_rooms_are_split_by_walls_with_doorways() {
+        let arena = laid_out(Layout::Rooms, Topology::BOX, 3);
+        let divider = BOARD_SIZE_X / ROOMS.0;
+
+        let column = (0..BOARD_SIZE_Y)
+            .filter(|&y| !arena.is_wall(&Point::new(y, divider)))
+            .count();
+        assert_eq!(column, ROOMS.1 as usize * DOORWAY as usize);
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        assert_eq!("Maze".parse::<Layout>().unwrap(), Layout::Maze);
+        assert_eq!(Layout::Spiral.to_string(), "spiral");
+        assert!("labyrinth".parse::<Layout>().is_err());
+    }
+}
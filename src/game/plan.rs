@@ -0,0 +1,278 @@
+use super::movement::PlanError;
+use super::point::Direction;
+
+use snake_proto::{PlanReceipt, PlannedMove};
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use tracing::debug;
+
+/// Moves a single plan may hold, about ten seconds of play at the default speed
+pub const MAX_PLAN_LENGTH: usize = 100;
+/// Ticks past the current one a plan may reach, about a minute of play at the default speed
+pub const MAX_PLAN_HORIZON: u64 = 600;
+
+#[derive(Debug)]
+struct Plan {
+    id: u64,
+    owner: IpAddr,
+    /// Ticks are strictly increasing
+    moves: VecDeque<(u64, Direction)>,
+}
+
+/// Plans of the game in progress, every client has at most one so it gets one planned vote per tick
+#[derive(Debug, Default)]
+pub struct MovePlans {
+    last_id: u64,
+    plans: Vec<Plan>,
+}
+
+impl MovePlans {
+    /// Schedules `moves` of `owner` after `tick`, replacing what is left of its previous plan
+    pub fn add(
+        &mut self,
+        owner: IpAddr,
+        moves: &[PlannedMove],
+        tick: u64,
+    ) -> Result<PlanReceipt, PlanError> {
+        let moves = schedule(moves, tick)?;
+        self.last_id += 1;
+        let receipt = PlanReceipt {
+            id: self.last_id,
+            first_tick: moves.front().map_or(tick, |(tick, _)| *tick),
+            last_tick: moves.back().map_or(tick, |(tick, _)| *tick),
+        };
+
+        self.plans.retain(|plan| plan.owner != owner);
+        self.plans.push(Plan {
+            id: receipt.id,
+            owner,
+            moves,
+        });
+
+        Ok(receipt)
+    }
+
+    /// Drops the remaining moves of plan `id`, only its owner may do so
+    pub fn cancel(&mut self, owner: IpAddr, id: u64) -> Result<(), PlanError> {
+        let plans = self.plans.len();
+        self.plans
+            .retain(|plan| plan.id != id || plan.owner != owner);
+
+        match self.plans.len() < plans {
+            true => Ok(()),
+            false => Err(PlanError::NotFound(id)),
+        }
+    }
+
+    /// Whether the plan of `owner` has a move for `tick`
+    pub fn has_move(&self, owner: IpAddr, tick: u64) -> bool {
+        self.plans
+            .iter()
+            .filter(|plan| plan.owner == owner)
+            .any(|plan| plan.moves.iter().any(|(planned, _)| *planned == tick))
+    }
+
+    /// Directions planned for `tick`, moves for earlier ticks which were missed are dropped
+    pub fn take_due(&mut self, tick: u64) -> Vec<Direction> {
+        let mut due = vec![];
+        for plan in &mut self.plans {
+            while let Some(&(planned, direction)) = plan.moves.front() {
+                if planned > tick {
+                    break;
+                }
+                plan.moves.pop_front();
+
+                match planned == tick {
+                    true => due.push(direction),
+                    false => debug!("Move of plan {} for tick {} was missed", plan.id, planned),
+                }
+            }
+        }
+        self.plans.retain(|plan| !plan.moves.is_empty());
+
+        due
+    }
+
+    pub fn clear(&mut self) {
+        self.plans.clear();
+    }
+}
+
+/// Resolves the tick of every move, the earliest possible one being the tick after `tick`
+fn schedule(moves: &[PlannedMove], tick: u64) -> Result<VecDeque<(u64, Direction)>, PlanError> {
+    if moves.is_empty() {
+        return Err(PlanError::Empty);
+    }
+    if moves.len() > MAX_PLAN_LENGTH {
+        return Err(PlanError::TooLong(moves.len()));
+    }
+
+    let last = tick.saturating_add(MAX_PLAN_HORIZON);
+    let mut previous = tick;
+    moves
+        .iter()
+        .map(|planned| {
+            let planned_tick = match planned.tick {
+                Some(planned_tick) if planned_tick <= tick => {
+                    return Err(PlanError::TickPassed {
+                        tick: planned_tick,
+                        next: tick.saturating_add(1),
+                    })
+                }
+                Some(planned_tick) if planned_tick <= previous => {
+                    return Err(PlanError::OutOfOrder {
+                        tick: planned_tick,
+                        previous,
+                    })
+                }
+                Some(planned_tick) => planned_tick,
+                None => previous.checked_add(1).ok_or(PlanError::TooFarAhead {
+                    tick: previous,
+                    last,
+                })?,
+            };
+            if planned_tick > last {
+                return Err(PlanError::TooFarAhead {
+                    tick: planned_tick,
+                    last,
+                });
+            }
+            previous = planned_tick;
+
+            Ok((planned_tick, planned.direction))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::net::Ipv4Addr;
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    const OTHER_CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+    fn at(direction: Direction, tick: u64) -> PlannedMove {
+        PlannedMove {
+            direction,
+            tick: Some(tick),
+        }
+    }
+
+    #[test]
+    fn test_untagged_moves_follow_each_other() {
+        let moves = [
+            Direction::Left.into(),
+            at(Direction::Up, 15),
+            Direction::Right.into(),
+        ];
+
+        assert_eq!(
+            schedule(&moves, 10),
+            Ok(VecDeque::from([
+                (11, Direction::Left),
+                (15, Direction::Up),
+                (16, Direction::Right),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_rejects_passed_and_unordered_ticks() {
+        assert_eq!(
+            schedule(&[at(Direction::Up, 10)], 10),
+            Err(PlanError::TickPassed { tick: 10, next: 11 })
+        );
+        assert_eq!(
+            schedule(&[at(Direction::Up, 14), at(Direction::Left, 12)], 10),
+            Err(PlanError::OutOfOrder {
+                tick: 12,
+                previous: 14
+            })
+        );
+        assert_eq!(schedule(&[], 10), Err(PlanError::Empty));
+    }
+
+    #[test]
+    fn test_rejects_ticks_beyond_horizon() {
+        let last = 10 + MAX_PLAN_HORIZON;
+
+        assert_eq!(
+            schedule(&[at(Direction::Up, u64::MAX), Direction::Left.into()], 10),
+            Err(PlanError::TooFarAhead {
+                tick: u64::MAX,
+                last
+            })
+        );
+        assert_eq!(
+            schedule(&[at(Direction::Up, last), Direction::Left.into()], 10),
+            Err(PlanError::TooFarAhead {
+                tick: last + 1,
+                last
+            })
+        );
+        assert_eq!(
+            schedule(
+                &[at(Direction::Up, u64::MAX), Direction::Left.into()],
+                u64::MAX - 1
+            ),
+            Err(PlanError::TooFarAhead {
+                tick: u64::MAX,
+                last: u64::MAX
+            })
+        );
+    }
+
+    #[test]
+    fn test_due_moves_are_taken_in_order() {
+        let mut plans = MovePlans::default();
+        let receipt = plans
+            .add(CLIENT, &[Direction::Left.into(), Direction::Up.into()], 10)
+            .unwrap();
+
+        assert_eq!(receipt.first_tick, 11);
+        assert_eq!(receipt.last_tick, 12);
+        assert!(plans.has_move(CLIENT, 11));
+        assert!(!plans.has_move(OTHER_CLIENT, 11));
+        assert_eq!(plans.take_due(11), vec![Direction::Left]);
+        assert!(!plans.has_move(CLIENT, 11));
+        assert_eq!(plans.take_due(12), vec![Direction::Up]);
+        assert!(plans.plans.is_empty());
+    }
+
+    #[test]
+    fn test_missed_moves_are_dropped() {
+        let mut plans = MovePlans::default();
+        plans
+            .add(CLIENT, &[Direction::Left.into(), Direction::Up.into()], 10)
+            .unwrap();
+
+        assert_eq!(plans.take_due(12), vec![Direction::Up]);
+    }
+
+    #[test]
+    fn test_new_plan_replaces_the_previous_one() {
+        let mut plans = MovePlans::default();
+        plans.add(CLIENT, &[Direction::Left.into()], 10).unwrap();
+        plans
+            .add(OTHER_CLIENT, &[Direction::Up.into()], 10)
+            .unwrap();
+        plans.add(CLIENT, &[Direction::Right.into()], 10).unwrap();
+
+        assert_eq!(plans.take_due(11), vec![Direction::Up, Direction::Right]);
+    }
+
+    #[test]
+    fn test_only_owner_cancels() {
+        let mut plans = MovePlans::default();
+        let receipt = plans.add(CLIENT, &[Direction::Left.into()], 10).unwrap();
+
+        assert_eq!(
+            plans.cancel(OTHER_CLIENT, receipt.id),
+            Err(PlanError::NotFound(receipt.id))
+        );
+        assert_eq!(plans.cancel(CLIENT, receipt.id), Ok(()));
+        assert_eq!(plans.take_due(11), vec![]);
+    }
+}
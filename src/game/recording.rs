@@ -0,0 +1,445 @@
+use super::arena::Arena;
+use super::board::{Board, CellSymbol};
+use super::fruit::Fruit;
+use super::image::{self, ImageError, ImageOptions};
+use super::point::{Direction, Point};
+use super::runner::remove_eaten_fruits;
+use super::snake::Snake;
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Once that many events are recorded, the older half is folded into the starting keyframe
+const MAX_RECORDED_EVENTS: usize = 20_000;
+/// Most frames a replay has, longer ones skip frames evenly
+pub const MAX_REPLAY_FRAMES: usize = 1_000;
+const LAST_FRAME_DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    FruitSpawned(Point),
+    Moved(Option<Direction>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Keyframe {
+    elapsed: Duration,
+    snake: Snake,
+    fruits: Vec<Fruit>,
+}
+
+impl Keyframe {
+    fn apply(&mut self, arena: &Arena, elapsed: Duration, event: RecordedEvent) {
+        self.elapsed = elapsed;
+        match event {
+            RecordedEvent::FruitSpawned(point) => self.fruits.push(Fruit { point }),
+            RecordedEvent::Moved(direction) => {
+                // Biting itself ends the game, which is the last recorded move anyway
+                let _ = self.snake.make_move(arena, direction);
+                if remove_eaten_fruits(&mut self.fruits, self.snake.head().unwrap()) {
+                    self.snake.increase_snake_command();
+                }
+            }
+        }
+    }
+
+    fn draw(&self, arena: &Arc<Arena>) -> Board {
+        let mut board = Board::new(Arc::clone(arena));
+
+        self.fruits
+            .iter()
+            .for_each(|f| board.change_cell_symbol(&f.point, CellSymbol::Fruit));
+        self.snake
+            .get_occupied_points()
+            .iter()
+            .for_each(|p| board.change_cell_symbol(p, CellSymbol::Snake));
+        board.change_cell_symbol(self.snake.head().unwrap(), CellSymbol::SnakeHead);
+
+        board
+    }
+}
+
+/// Single frame of a replayed recording, `elapsed` is counted from the start of the game
+#[derive(Debug)]
+pub struct ReplayFrame {
+    pub elapsed: Duration,
+    pub board: Board,
+}
+
+/// Moves and fruit spawns of a single game, enough to replay it frame by frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    #[serde(skip, default = "Instant::now")]
+    started_at: Instant,
+    /// Arena the whole game is played in
+    arena: Arc<Arena>,
+    start: Keyframe,
+    events: VecDeque<(Duration, RecordedEvent)>,
+}
+
+impl Recording {
+    pub fn new(started_at: Instant) -> Self {
+        Self::with_arena(started_at, Arc::default())
+    }
+
+    pub fn with_arena(started_at: Instant, arena: Arc<Arena>) -> Self {
+        Self {
+            started_at,
+            arena,
+            start: Keyframe {
+                elapsed: Duration::ZERO,
+                snake: Snake::default(),
+                fruits: vec![],
+            },
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: RecordedEvent) {
+        self.record_at(self.started_at.elapsed(), event);
+    }
+
+    fn record_at(&mut self, elapsed: Duration, event: RecordedEvent) {
+        self.events.push_back((elapsed, event));
+
+        if self.events.len() > MAX_RECORDED_EVENTS {
+            debug!("Compacting recording of {} events", self.events.len());
+            for (elapsed, event) in self.events.drain(..MAX_RECORDED_EVENTS / 2) {
+                self.start.apply(&self.arena, elapsed, event);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Writes the recording as JSON, so it can be replayed after a restart
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(file, self).map_err(io::Error::from)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        serde_json::from_reader(file).map_err(io::Error::from)
+    }
+
+    /// Time between the start of the game and the last recorded event
+    pub fn duration(&self) -> Duration {
+        self.events
+            .back()
+            .map(|(elapsed, _)| *elapsed)
+            .unwrap_or(self.start.elapsed)
+    }
+
+    /// Replays the recording, producing a frame after every move which happened
+    /// within the last `last` of the game (or during the whole game if `None`).
+    /// Past [`MAX_REPLAY_FRAMES`] moves only every n-th one gets a frame, the last one always does
+    pub fn replay(&self, last: Option<Duration>) -> Vec<ReplayFrame> {
+        let since = last
+            .map(|last| self.duration().saturating_sub(last))
+            .unwrap_or_default();
+        let is_shown = |elapsed: Duration, event: RecordedEvent| {
+            matches!(event, RecordedEvent::Moved(_)) && elapsed >= since
+        };
+        let moves = self
+            .events
+            .iter()
+            .filter(|&&(elapsed, event)| is_shown(elapsed, event))
+            .count();
+        let stride = moves.div_ceil(MAX_REPLAY_FRAMES).max(1);
+        let mut state = self.start.clone();
+        let mut shown = 0;
+        let mut frames = vec![];
+
+        for &(elapsed, event) in &self.events {
+            state.apply(&self.arena, elapsed, event);
+
+            if is_shown(elapsed, event) {
+                shown += 1;
+                if (moves - shown) % stride == 0 {
+                    frames.push(ReplayFrame {
+                        elapsed,
+                        board: state.draw(&self.arena),
+                    });
+                }
+            }
+        }
+
+        frames
+    }
+
+    /// Renders the replayed frames into an animated GIF, playing at the speed of the game
+    pub fn render_gif(
+        &self,
+        last: Option<Duration>,
+        options: &ImageOptions,
+    ) -> Result<Vec<u8>, ImageError> {
+        let frames = self.replay(last);
+        let mut shown_for: Vec<Duration> = frames
+            .windows(2)
+            .map(|pair| pair[1].elapsed - pair[0].elapsed)
+            .collect();
+        // Hold the last frame as long as the one before it
+        shown_for.push(
+            shown_for
+                .last()
+                .copied()
+                .unwrap_or(LAST_FRAME_DEFAULT_DELAY),
+        );
+
+        image::render_gif(frames.iter().map(|f| &f.board).zip(shown_for), options)
+    }
+}
+
+/// Recording of the game in progress along with the one played before it
+#[derive(Debug)]
+pub struct GameRecorder {
+    current: Recording,
+    previous: Option<Recording>,
+}
+
+impl GameRecorder {
+    /// Starts recording a game played in `arena`
+    pub fn start_new_game(&mut self, arena: &Arc<Arena>) {
+        let finished = std::mem::replace(
+            &mut self.current,
+            Recording::with_arena(Instant::now(), Arc::clone(arena)),
+        );
+        if !finished.is_empty() {
+            self.previous = Some(finished);
+        }
+    }
+
+    pub fn record(&mut self, event: RecordedEvent) {
+        self.current.record(event);
+    }
+
+    pub fn current(&self) -> &Recording {
+        &self.current
+    }
+
+    pub fn previous(&self) -> Option<&Recording> {
+        self.previous.as_ref()
+    }
+
+    /// Game in progress, or the previous one if the current has not recorded anything yet
+    pub fn latest(&self) -> Option<&Recording> {
+        match self.current.is_empty() {
+            false => Some(&self.current),
+            true => self.previous(),
+        }
+    }
+
+    /// Makes a game recorded before a restart replayable as the previous one
+    pub fn restore(&mut self, recording: Recording) {
+        self.previous = Some(recording);
+    }
+}
+
+impl Default for GameRecorder {
+    fn default() -> Self {
+        Self {
+            current: Recording::new(Instant::now()),
+            previous: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::board::get_center_of_board_coordinates;
+    use super::super::point::Topology;
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn test_replay_produces_frame_per_move() {
+        let mut recording = Recording::new(Instant::now());
+        recording.record_at(secs(0), RecordedEvent::FruitSpawned(Point::new(0, 0)));
+        recording.record_at(secs(1), RecordedEvent::Moved(None));
+        recording.record_at(secs(2), RecordedEvent::Moved(Some(Direction::Left)));
+
+        let frames = recording.replay(None);
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].elapsed, secs(2));
+    }
+
+    #[test]
+    fn test_replay_only_last_seconds() {
+        let mut recording = Recording::new(Instant::now());
+        (0..10).for_each(|i| recording.record_at(secs(i), RecordedEvent::Moved(None)));
+
+        let frames = recording.replay(Some(secs(3)));
+
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].elapsed, secs(6));
+    }
+
+    #[test]
+    fn test_long_replay_skips_frames() {
+        let mut recording = Recording::new(Instant::now());
+        (0..(2 * MAX_REPLAY_FRAMES as u64 + 1))
+            .for_each(|i| recording.record_at(secs(i), RecordedEvent::Moved(None)));
+
+        let frames = recording.replay(None);
+
+        assert!(frames.len() <= MAX_REPLAY_FRAMES);
+        assert_eq!(frames[1].elapsed - frames[0].elapsed, secs(3));
+        assert_eq!(
+            frames.last().unwrap().elapsed,
+            secs(2 * MAX_REPLAY_FRAMES as u64)
+        );
+    }
+
+    #[test]
+    fn test_replay_snake_eats_recorded_fruit() {
+        let center = get_center_of_board_coordinates();
+        let mut recording = Recording::new(Instant::now());
+        recording.record_at(
+            secs(0),
+            RecordedEvent::FruitSpawned(Point::new(center.y - 1, center.x)),
+        );
+        recording.record_at(secs(1), RecordedEvent::Moved(None));
+        recording.record_at(secs(2), RecordedEvent::Moved(None));
+
+        let frames = recording.replay(None);
+        let snake_cells = |board: &Board| {
+            board
+                .rows()
+                .flatten()
+                .filter(|c| matches!(c, CellSymbol::Snake | CellSymbol::SnakeHead))
+                .count()
+        };
+
+        assert_eq!(snake_cells(&frames[0].board), 3);
+        assert_eq!(snake_cells(&frames[1].board), 4);
+    }
+
+    #[test]
+    fn test_compacting_keeps_replay_consistent() {
+        let mut recording = Recording::new(Instant::now());
+        let mut reference = Keyframe {
+            elapsed: Duration::ZERO,
+            snake: Snake::default(),
+            fruits: vec![],
+        };
+        let directions = [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Up,
+        ];
+
+        for i in 0..(MAX_RECORDED_EVENTS + 1) {
+            let event = RecordedEvent::Moved(Some(directions[i % directions.len()]));
+            recording.record_at(Duration::from_millis(i as u64), event);
+            reference.apply(&recording.arena, Duration::from_millis(i as u64), event);
+        }
+
+        assert_eq!(recording.events.len(), MAX_RECORDED_EVENTS / 2 + 1);
+        assert_eq!(
+            recording.replay(Some(Duration::ZERO))[0]
+                .board
+                .rows()
+                .collect::<Vec<_>>(),
+            reference.draw(&recording.arena).rows().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_render_gif_of_recording() {
+        let mut recording = Recording::new(Instant::now());
+        (0..3).for_each(|i| recording.record_at(secs(i), RecordedEvent::Moved(None)));
+
+        let gif = recording
+            .render_gif(None, &ImageOptions::default())
+            .unwrap();
+
+        assert_eq!(&gif[..6], b"GIF89a");
+    }
+
+    #[test]
+    fn test_recorder_keeps_previous_non_empty_game() {
+        let mut recorder = GameRecorder::default();
+        recorder.record(RecordedEvent::Moved(None));
+
+        recorder.start_new_game(&Arc::default());
+        recorder.start_new_game(&Arc::default());
+
+        assert!(recorder.current().is_empty());
+        assert!(!recorder.previous().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_saved_recording_replays_the_same() {
+        let path =
+            std::env::temp_dir().join(format!("snake-recording-{}.json", std::process::id()));
+        let mut recording = Recording::new(Instant::now());
+        recording.record_at(secs(0), RecordedEvent::FruitSpawned(Point::new(1, 1)));
+        recording.record_at(secs(1), RecordedEvent::Moved(Some(Direction::Left)));
+
+        recording.save(&path).unwrap();
+        let loaded = Recording::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let replayed = |recording: &Recording| -> Vec<(Duration, Vec<Vec<CellSymbol>>)> {
+            recording
+                .replay(None)
+                .into_iter()
+                .map(|frame| {
+                    (
+                        frame.elapsed,
+                        frame.board.rows().map(<[_]>::to_vec).collect(),
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(replayed(&loaded.unwrap()), replayed(&recording));
+    }
+
+    #[test]
+    fn test_arena_is_saved_once() {
+        let mut arena = Arena::new(Topology::BOX);
+        arena
+            .add_portals(Point::new(1, 1), Point::new(5, 5))
+            .unwrap();
+        let mut recording = Recording::with_arena(Instant::now(), Arc::new(arena.clone()));
+        recording.record_at(secs(0), RecordedEvent::Moved(None));
+
+        let json = serde_json::to_value(&recording).unwrap();
+        let loaded: Recording = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(json["start"]["snake"].get("topology"), None);
+        assert_eq!(*loaded.arena, arena);
+        assert_eq!(
+            loaded.replay(None)[0].board.arena().topology(),
+            Topology::BOX
+        );
+    }
+
+    #[test]
+    fn test_restored_recording_is_the_previous_game() {
+        let mut recorder = GameRecorder::default();
+        assert!(recorder.latest().is_none());
+
+        let mut recording = Recording::new(Instant::now());
+        recording.record_at(secs(0), RecordedEvent::Moved(None));
+        recorder.restore(recording);
+
+        assert!(!recorder.previous().unwrap().is_empty());
+        assert!(!recorder.latest().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+use super::board::{CellSymbol, Wall};
+
+use std::fmt::{Result, Write};
+use thiserror::Error;
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_BOLD_GREEN: &str = "\x1b[1;92m";
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_RED: &str = "\x1b[1;31m";
+const ANSI_BLUE: &str = "\x1b[34m";
+const ANSI_MAGENTA: &str = "\x1b[1;35m";
+
+pub trait Renderer {
+    fn write_cell(&self, wr: &mut dyn Write, cell: CellSymbol) -> Result;
+
+    fn write_line_end(&self, wr: &mut dyn Write) -> Result {
+        wr.write_char('\n')
+    }
+}
+
+/// Monochrome box-drawing output, the format `GET /snake` always returned
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainRenderer;
+
+impl Renderer for PlainRenderer {
+    fn write_cell(&self, wr: &mut dyn Write, cell: CellSymbol) -> Result {
+        wr.write_char(cell.to_char())
+    }
+}
+
+/// Box-drawing output with ANSI colour escapes for the snake, fruits and walls
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnsiRenderer;
+
+impl Renderer for AnsiRenderer {
+    fn write_cell(&self, wr: &mut dyn Write, cell: CellSymbol) -> Result {
+        let colour = match cell {
+            CellSymbol::Board => return wr.write_char(cell.to_char()),
+            CellSymbol::SnakeHead => ANSI_BOLD_GREEN,
+            CellSymbol::Snake => ANSI_GREEN,
+            CellSymbol::Fruit => ANSI_RED,
+            CellSymbol::Wall(_) | CellSymbol::Junction(_) => ANSI_BLUE,
+            CellSymbol::Portal(_) => ANSI_MAGENTA,
+        };
+        write!(wr, "{colour}{}{ANSI_RESET}", cell.to_char())
+    }
+}
+
+/// Pure ASCII output for terminals without Unicode support
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiRenderer;
+
+impl Renderer for AsciiRenderer {
+    fn write_cell(&self, wr: &mut dyn Write, cell: CellSymbol) -> Result {
+        let symbol = match cell {
+            CellSymbol::Wall(Wall::NS) => '|',
+            CellSymbol::Wall(Wall::EW) => '-',
+            CellSymbol::Wall(Wall::SolidNS) => '!',
+            CellSymbol::Wall(Wall::SolidEW) => '=',
+            CellSymbol::Wall(Wall::MirrorNS) => ':',
+            CellSymbol::Wall(Wall::MirrorEW) => '~',
+            CellSymbol::Wall(Wall::Block) => 'X',
+            CellSymbol::Junction(_) => '+',
+            _ => cell.to_char(),
+        };
+        wr.write_char(symbol)
+    }
+}
+
+/// Emoji output, every cell is two columns wide so the board stays aligned
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmojiRenderer;
+
+impl Renderer for EmojiRenderer {
+    fn write_cell(&self, wr: &mut dyn Write, cell: CellSymbol) -> Result {
+        let symbol = match cell {
+            // Ideographic space is as wide as an emoji, unlike a plain one
+            CellSymbol::Board => "\u{3000}",
+            CellSymbol::Snake => "🟩",
+            CellSymbol::SnakeHead => "🐍",
+            CellSymbol::Fruit => "🍎",
+            CellSymbol::Wall(_) | CellSymbol::Junction(_) => "🧱",
+            CellSymbol::Portal(_) => "🌀",
+        };
+        wr.write_str(symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderStyle {
+    #[default]
+    Plain,
+    Ansi,
+    Ascii,
+    Emoji,
+}
+
+impl RenderStyle {
+    pub fn renderer(&self) -> &'static dyn Renderer {
+        match self {
+            Self::Plain => &PlainRenderer,
+            Self::Ansi => &AnsiRenderer,
+            Self::Ascii => &AsciiRenderer,
+            Self::Emoji => &EmojiRenderer,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RenderStyleError {
+    #[error("There are 4 possible styles: ['plain', 'ansi', 'ascii', 'emoji']. `{0}` does not match any of them")]
+    ConversionFromStringError(String),
+}
+
+impl TryFrom<String> for RenderStyle {
+    type Error = RenderStyleError;
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "plain" => Ok(Self::Plain),
+            "ansi" => Ok(Self::Ansi),
+            "ascii" => Ok(Self::Ascii),
+            "emoji" => Ok(Self::Emoji),
+            _ => Err(RenderStyleError::ConversionFromStringError(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::board::Board;
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn render(style: RenderStyle) -> String {
+        let mut out = String::new();
+        Board::default().render(style.renderer(), &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_plain_style_matches_get_board() {
+        let mut expected = String::new();
+        Board::default().get_board(&mut expected).unwrap();
+
+        assert_eq!(render(RenderStyle::Plain), expected);
+    }
+
+    #[test]
+    fn test_ascii_style_uses_only_ascii() {
+        let rendered = render(RenderStyle::Ascii);
+
+        assert!(rendered.is_ascii());
+        assert!(rendered.starts_with("+----"));
+    }
+
+    #[test]
+    fn test_ansi_style_resets_colour_after_each_wall() {
+        let rendered = render(RenderStyle::Ansi);
+        let first_line = rendered.lines().next().unwrap();
+
+        assert!(first_line.starts_with(&format!("{ANSI_BLUE}┌{ANSI_RESET}")));
+    }
+
+    #[test]
+    fn test_emoji_style_keeps_rows_equally_wide() {
+        let rendered = render(RenderStyle::Emoji);
+        let widths: Vec<usize> = rendered.lines().map(|l| l.chars().count()).collect();
+
+        assert!(widths.windows(2).all(|w| w[0] == w[1]));
+    }
+
+    #[test]
+    fn test_style_from_string() {
+        assert_eq!(
+            RenderStyle::try_from("ANSI".to_owned()).unwrap(),
+            RenderStyle::Ansi
+        );
+        assert!(RenderStyle::try_from("sixel".to_owned()).is_err());
+    }
+}
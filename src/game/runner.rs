@@ -1,15 +1,23 @@
+use super::arena::Arena;
 use super::board::{generate_points_pool, Board, CellSymbol};
+use super::campaign::{GameMode, Goal, Rules};
 use super::consts::*;
 use super::fruit::Fruit;
+use super::heartbeat::{Heartbeat, Phase};
 use super::point::{Direction, Point};
+use super::recording::{GameRecorder, RecordedEvent, Recording};
+use super::shutdown::{shutdown_signal, SHUTDOWN_DEADLINE};
 use super::snake::{Snake, SnakeError};
+use super::snapshot::{Snapshots, TickTiming};
+use crate::metrics;
 use crate::server;
+use actix_web::dev::ServerHandle;
 use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
 use std::sync::RwLock;
 
-use tokio::signal;
 use tokio::sync::broadcast;
-use tokio::sync::broadcast::error::TryRecvError;
 
 use super::commands::{MoveCommandIssuer, MoveCommandReceiver};
 
@@ -18,88 +26,244 @@ use rand::prelude::*;
 use std::sync::Arc;
 
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tokio::time::{interval_at, Duration, Instant, Interval, MissedTickBehavior};
 use tracing::{debug, error, info, warn};
 
 const MOVE_COMMAND_CHANNEL_SIZE: usize = 1000;
+const MISSED_TICK_THRESHOLD: Duration = Duration::from_millis(5);
 
 pub async fn game_loop(
-    terminal_signal_tx: broadcast::Sender<()>,
+    handles: GameHandles,
     command_receiver: MoveCommandReceiver,
-    order_move: Arc<RwLock<MoveCommandIssuer>>,
-    board: Arc<RwLock<Board>>,
+    mut shutdown_signal_recv: broadcast::Receiver<()>,
     fps: f32,
+    mut mode: GameMode,
 ) {
-    let mut game = Game::new(command_receiver, Arc::clone(&board), fps);
-    loop {
-        let rx = terminal_signal_tx.subscribe();
-        tokio::select! {
-            _ = game.start(rx) => {
-                let (command_sender, command_recv) = mpsc::channel(MOVE_COMMAND_CHANNEL_SIZE);
-
-                let command_receiver = command_recv.into();
-                order_move.write().unwrap().set_issuer(command_sender);
-                *board.write().unwrap() = Board::default();
+    // Every game is played in a freshly laid out arena, which is recorded along with it
+    let new_game = |command_receiver, mode: &mut GameMode| {
+        let rules = mode.next_game(fps);
+        handles.heartbeat.write().unwrap().set_fps(rules.fps);
+        handles.limiter.set_fps(rules.fps);
+        handles
+            .recorder
+            .write()
+            .unwrap()
+            .start_new_game(&rules.arena);
+        Game::new(
+            command_receiver,
+            Arc::clone(&handles.snapshots),
+            Arc::clone(&handles.recorder),
+            Arc::clone(&handles.heartbeat),
+            rules,
+        )
+    };
 
-                game = Game::new(command_receiver, Arc::clone(&board), fps);
-            }
-            _ = signal::ctrl_c() => {
-                if let Err(err) = terminal_signal_tx.send(()) {
-                    warn!("Termination signal {}", err)
-                };
-                break;
-            }
+    let mut game = new_game(command_receiver, &mut mode);
+    loop {
+        let end = game.start(&mut shutdown_signal_recv).await;
+        if end == GameEnd::Shutdown {
+            break;
         }
+        mode.finish(end);
+        let (command_sender, command_recv) = mpsc::channel(MOVE_COMMAND_CHANNEL_SIZE);
+
+        let command_receiver = {
+            let mut order_move = handles.order_move.write().unwrap();
+            order_move.set_issuer(command_sender);
+            order_move.receiver(command_recv)
+        };
+        game = new_game(command_receiver, &mut mode);
     }
+
+    handles.heartbeat.write().unwrap().set_phase(Phase::Stopped);
 }
 
-pub async fn new_game(fps: f32) {
+/// Shared handles to a running game loop
+#[derive(Debug, Clone)]
+pub struct GameHandles {
+    pub order_move: Arc<RwLock<MoveCommandIssuer>>,
+    pub snapshots: Arc<Snapshots>,
+    pub recorder: Arc<RwLock<GameRecorder>>,
+    pub heartbeat: Arc<RwLock<Heartbeat>>,
+    /// Shared, so HTTP and telnet players from the same address get a single vote
+    pub limiter: Arc<server::VoteLimiter>,
+    pub terminal_signal_tx: broadcast::Sender<()>,
+}
+
+/// Spawns the game loop on the current runtime without any server in front of it
+pub fn spawn_game(fps: f32, mode: GameMode) -> (GameHandles, JoinHandle<()>) {
     // Movement command channels
     let (command_sender, command_recv) = mpsc::channel(MOVE_COMMAND_CHANNEL_SIZE);
 
-    // Arc<RwLock<Board>> since these variables/objects are read from other thread
-    let board = Arc::new(RwLock::new(Board::default()));
-    let order_move = Arc::new(RwLock::new(MoveCommandIssuer::from(command_sender)));
-
-    let server_running = server::run(Arc::clone(&order_move), Arc::clone(&board));
-
-    // Termination signal channel
-    let (terminal_signal_tx, _) = broadcast::channel(1);
-    let tx_for_server = terminal_signal_tx.clone();
+    // Shared, since these variables/objects are read from other thread
+    let snapshots = Arc::new(Snapshots::default());
+    let order_move = MoveCommandIssuer::from(command_sender);
+    let command_receiver = order_move.receiver(command_recv);
+    let order_move = Arc::new(RwLock::new(order_move));
+    let recorder = Arc::new(RwLock::new(GameRecorder::default()));
+    let heartbeat = Arc::new(RwLock::new(Heartbeat::new(fps)));
+    let limiter = Arc::new(server::VoteLimiter::new(fps));
+
+    // Termination signal channel, subscribed before the game loop runs so no signal is missed
+    let (terminal_signal_tx, shutdown_signal_recv) = broadcast::channel(1);
+
+    let handles = GameHandles {
+        order_move,
+        snapshots,
+        recorder,
+        heartbeat,
+        limiter,
+        terminal_signal_tx,
+    };
 
     // Spawn thread with game loop
+    let game_loop_handles = handles.clone();
     let game_loop_task = tokio::spawn(async move {
         game_loop(
-            terminal_signal_tx,
-            command_recv.into(),
-            order_move,
-            board,
+            game_loop_handles,
+            command_receiver,
+            shutdown_signal_recv,
             fps,
+            mode,
         )
         .await
     });
 
-    // End program when one of its components is done
+    (handles, game_loop_task)
+}
+
+pub async fn new_game(fps: f32, recording_file: Option<PathBuf>, mode: GameMode) {
+    let (handles, game_loop_task) = spawn_game(fps, mode);
+
+    if let Some(path) = &recording_file {
+        match Recording::load(path) {
+            Ok(recording) => handles.recorder.write().unwrap().restore(recording),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => warn!(
+                "Restoring recording from {} failed: {}",
+                path.display(),
+                err
+            ),
+        }
+    }
+
+    let server = match server::run(
+        Arc::clone(&handles.order_move),
+        Arc::clone(&handles.snapshots),
+        Arc::clone(&handles.recorder),
+        Arc::clone(&handles.heartbeat),
+        Arc::clone(&handles.limiter),
+    ) {
+        Ok(server) => server,
+        Err(err) => {
+            error!("Web server failed to start: {}", err);
+            return;
+        }
+    };
+    let server_handle = server.handle();
+    let mut server_running = tokio::spawn(server);
+    let telnet_running = tokio::spawn(server::run_telnet(
+        server::TELNET_PORT,
+        Arc::clone(&handles.order_move),
+        Arc::clone(&handles.snapshots),
+        Arc::clone(&handles.limiter),
+    ));
+    let mut game_loop_task = game_loop_task;
+
+    // Shut down when asked to, or when the web server or the game is done. Telnet is left
+    // out, it is optional and never takes the rest down with it
     tokio::select! {
-        _ = server_running => {
-            if let Err(err) = tx_for_server.send(()) {
-                error!("{}", err)
-            };
+        _ = shutdown_signal() => {
+            info!("Shutting down");
         }
-        _ = game_loop_task => {
+        result = &mut server_running => {
+            if let Ok(Err(err)) = result {
+                error!("Web server failed: {}", err)
+            }
+        }
+        _ = &mut game_loop_task => {
             warn!("Game loop finished");
         }
     }
+
+    let shutdown = shut_down(
+        handles,
+        server_handle,
+        game_loop_task,
+        telnet_running,
+        recording_file,
+    );
+    if tokio::time::timeout(SHUTDOWN_DEADLINE, shutdown)
+        .await
+        .is_err()
+    {
+        warn!(
+            "Shutdown did not finish within {:?}, exiting anyway",
+            SHUTDOWN_DEADLINE
+        );
+    }
+}
+
+/// Stops taking commands, lets the game loop finish its tick, says goodbye to streaming
+/// clients while draining requests and finally saves the recording of the last game
+async fn shut_down(
+    handles: GameHandles,
+    server_handle: ServerHandle,
+    game_loop_task: JoinHandle<()>,
+    telnet_running: JoinHandle<()>,
+    recording_file: Option<PathBuf>,
+) {
+    // Also fails readiness checks, so no new players are sent this way
+    handles.order_move.write().unwrap().close();
+
+    // Nobody listens anymore if the game loop is already done
+    let _ = handles.terminal_signal_tx.send(());
+    if !game_loop_task.is_finished() {
+        let _ = game_loop_task.await;
+    }
+
+    // Streams end after a final message, which lets the servers drain
+    handles.snapshots.close();
+    server_handle.stop(true).await;
+    if !telnet_running.is_finished() {
+        let _ = telnet_running.await;
+    }
+    info!("Clients drained");
+
+    if let Some(path) = recording_file {
+        let recorder = handles.recorder.read().unwrap();
+        if let Some(recording) = recorder.latest() {
+            match recording.save(&path) {
+                Ok(()) => info!("Saved recording to {}", path.display()),
+                Err(err) => error!("Saving recording to {} failed: {}", path.display(), err),
+            }
+        }
+    }
 }
 
-fn create_game_action_interval(spf: f32) -> Interval {
-    let start = Instant::now() + Duration::from_secs(START_DELAY_IN_SECS);
+fn create_game_action_interval(start: Instant, spf: f32) -> Interval {
     let mut interval = interval_at(start, Duration::from_secs_f32(spf));
     interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
     interval
 }
 
+/// When the tick after the one scheduled for `scheduled`, which fired at `fired`, is due
+fn next_tick_after(scheduled: Instant, fired: Instant, period: Duration) -> Instant {
+    // Mirrors `MissedTickBehavior::Delay`, tokio considers a tick missed once it is that late
+    if fired > scheduled + MISSED_TICK_THRESHOLD {
+        fired + period
+    } else {
+        scheduled + period
+    }
+}
+
+/// Number of whole periods by which a tick scheduled for `scheduled` ran late
+fn count_missed_ticks(scheduled: Instant, now: Instant, period: Duration) -> u64 {
+    (now.saturating_duration_since(scheduled).as_secs_f64() / period.as_secs_f64()) as u64
+}
+
 #[tracing::instrument(skip(rng))]
 fn pick_move_direction_based_on_probabilities(
     issued_commands: &mut HashMap<Direction, u32>,
@@ -114,40 +278,86 @@ fn pick_move_direction_based_on_probabilities(
     Some(picked_direction)
 }
 
+/// Why a game has ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEnd {
+    GameOver,
+    /// The goal of the campaign stage has been reached
+    StageCleared,
+    Shutdown,
+}
+
 #[derive(Debug)]
 pub struct Game {
+    /// Tick of the last published snapshot, keeps increasing across games
+    tick: u64,
+    next_tick_at: Instant,
     score: u32,
     snake: Snake,
     fruits: Vec<Fruit>,
-    board: Arc<RwLock<Board>>,
+    arena: Arc<Arena>,
+    board: Board,
+    board_replaced: bool,
+    snapshots: Arc<Snapshots>,
+    recorder: Arc<RwLock<GameRecorder>>,
+    heartbeat: Arc<RwLock<Heartbeat>>,
     fps: f32,
+    max_fruits: usize,
+    /// Goal of the campaign stage, none outside of the campaign
+    goal: Option<Goal>,
     move_command_manager_recv: MoveCommandReceiver,
 }
 
 impl Game {
-    pub async fn start(&mut self, mut shutdown_signal_recv: broadcast::Receiver<()>) {
-        let mut interval = create_game_action_interval(self.convert_fps_to_spf());
+    /// Plays until the snake dies, the stage goal is reached or the shutdown signal arrives,
+    /// a tick in progress is always finished first
+    pub async fn start(&mut self, shutdown_signal_recv: &mut broadcast::Receiver<()>) -> GameEnd {
+        let start = Instant::now() + Duration::from_secs(START_DELAY_IN_SECS);
+        let mut interval = create_game_action_interval(start, self.convert_fps_to_spf());
+        self.next_tick_at = start;
         let mut direction_command_counters: HashMap<Direction, u32> = HashMap::with_capacity(3);
-        let points_pool: Vec<Point> = generate_points_pool();
+        // Fruits are never placed on walls or portals, as the snake cannot stop on them to eat
+        let points_pool: Vec<Point> = generate_points_pool()
+            .into_iter()
+            .filter(|point| self.arena.is_free(point))
+            .collect();
+        metrics::GAMES_PLAYED.inc();
+        self.heartbeat.write().unwrap().set_phase(Phase::Starting);
 
-        loop {
-            match shutdown_signal_recv.try_recv() {
-                Ok(_) | Err(TryRecvError::Closed) => break,
-                _ => {}
-            };
-
-            self.next_frame();
+        // Publish the starting position, later snapshots are published once per tick
+        self.next_frame();
 
+        loop {
             self.control_fruits(&points_pool);
+            let mut ticked = false;
             tokio::select! {
-                _ = interval.tick() => {
+                // Whatever the signal, including the sender being gone, it means stopping
+                _ = shutdown_signal_recv.recv() => return GameEnd::Shutdown,
+                scheduled = interval.tick() => {
+                    ticked = true;
+                    metrics::TICKS.inc();
+                    self.heartbeat.write().unwrap().tick();
+                    let fired = Instant::now();
+                    metrics::MISSED_TICKS.inc_by(count_missed_ticks(scheduled, fired, interval.period()));
+                    self.next_tick_at = next_tick_after(scheduled, fired, interval.period());
+                    // The tick resolves into the next snapshot, plans refer to that one
+                    self.move_command_manager_recv.take_planned(self.tick + 1, &mut direction_command_counters, self.snake.get_current_direction());
                     if self.control_movement(&mut direction_command_counters).is_none() {
-                        break
+                        return GameEnd::GameOver;
                     }
                 }
                 _ = self.move_command_manager_recv.wait_for_command_and_act(&mut direction_command_counters, self.snake.get_current_direction()) => { }
             }
             self.check_if_snake_ate_fruit();
+
+            if ticked {
+                self.next_frame();
+                if self.goal_reached() {
+                    metrics::STAGES_CLEARED.inc();
+                    info!("The player cleared the stage scoring: {}", self.score);
+                    return GameEnd::StageCleared;
+                }
+            }
         }
     }
 
@@ -160,14 +370,32 @@ impl Game {
             &mut thread_rng(),
         );
 
-        match self.snake.make_move(direction) {
+        self.recorder
+            .write()
+            .unwrap()
+            .record(RecordedEvent::Moved(direction));
+
+        match self.snake.make_move(&self.arena, direction) {
             Err(SnakeError::BitOffHisTail) => {
+                metrics::DEATHS
+                    .with_label_values(&[metrics::death::BIT_ITSELF])
+                    .inc();
                 info!(
                     "The player bit off his tails, ended up scoring: {}",
                     self.score
                 );
                 None
             }
+            Err(SnakeError::HitWall) => {
+                metrics::DEATHS
+                    .with_label_values(&[metrics::death::HIT_WALL])
+                    .inc();
+                info!(
+                    "The player ran into a wall, ended up scoring: {}",
+                    self.score
+                );
+                None
+            }
             Ok(_) => Some(()),
             Err(SnakeError::BodyIsEmpty) => {
                 // It won't get here since, there is no chance
@@ -178,7 +406,7 @@ impl Game {
     }
 
     fn control_fruits(&mut self, points_pool: &[Point]) {
-        if self.fruits.len() < 5 {
+        if self.fruits.len() < self.max_fruits {
             let occupied_snake = self.snake.get_occupied_points();
 
             // Get possible points to place a new fruit
@@ -191,6 +419,10 @@ impl Game {
             if let Some(fruit) =
                 Fruit::try_spawn_at_random_place(&next_frame_filtered_out_cells, self.fruits.len())
             {
+                self.recorder
+                    .write()
+                    .unwrap()
+                    .record(RecordedEvent::FruitSpawned(fruit.point));
                 self.fruits.push(fruit);
             };
         }
@@ -203,37 +435,89 @@ impl Game {
         }
     }
 
+    fn goal_reached(&self) -> bool {
+        let length = self.snake.get_occupied_points().len() as u32;
+        self.goal
+            .is_some_and(|goal| goal.is_reached(length, self.score))
+    }
+
     fn next_frame(&mut self) {
-        let mut board = self.board.write().unwrap();
+        metrics::SCORE.set(self.score as i64);
+        metrics::SNAKE_LENGTH.set(self.snake.get_occupied_points().len() as i64);
+
+        let board = &mut self.board;
+        board.set_score(self.score);
+        let mut changes = vec![];
+        let mut change = |point: &Point, symbol: CellSymbol| {
+            if board.cell_symbol(point) != symbol {
+                board.change_cell_symbol(point, symbol);
+                changes.push((*point, symbol));
+            }
+        };
 
         self.fruits
             .iter()
-            .for_each(|f| (*board).change_cell_symbol(&f.point, CellSymbol::Fruit));
+            .for_each(|f| change(&f.point, CellSymbol::Fruit));
 
         // Override new head cell with snake head symbol
-        (*board).change_cell_symbol(self.snake.head().unwrap(), CellSymbol::SnakeHead);
+        change(self.snake.head().unwrap(), CellSymbol::SnakeHead);
 
         // Override old head cell with snake body symbol
-        (*board).change_cell_symbol(self.snake.second_segment().unwrap(), CellSymbol::Snake);
+        change(self.snake.second_segment().unwrap(), CellSymbol::Snake);
 
         // Override old tail cell with board symbol
         if let Some(point) = self.snake.get_orphaned_tail() {
-            (*board).change_cell_symbol(point, CellSymbol::Board);
+            change(point, CellSymbol::Board);
         }
+
+        // The first frame of a game replaces the previous game's board as a whole
+        let changes = (!std::mem::take(&mut self.board_replaced)).then_some(changes);
+        let timing = TickTiming {
+            next_at: self.next_tick_at.into_std(),
+            period: Duration::from_secs_f32(self.convert_fps_to_spf()),
+        };
+        self.tick = self.snapshots.publish(
+            self.board.clone(),
+            self.snake.get_occupied_points().iter().copied().collect(),
+            *self.snake.get_current_direction(),
+            changes,
+            Some(timing),
+        );
     }
 
     fn new(
         move_command_manager_recv: MoveCommandReceiver,
-        board: Arc<RwLock<Board>>,
-        fps: f32,
+        snapshots: Arc<Snapshots>,
+        recorder: Arc<RwLock<GameRecorder>>,
+        heartbeat: Arc<RwLock<Heartbeat>>,
+        rules: Rules,
     ) -> Self {
+        let Rules {
+            arena,
+            fps,
+            max_fruits,
+            level,
+        } = rules;
+        let goal = level.as_ref().map(|level| level.goal);
+        let mut board = Board::new(Arc::clone(&arena));
+        board.set_level(level);
+
         Self {
+            tick: snapshots.load().tick,
+            next_tick_at: Instant::now(),
             move_command_manager_recv,
             fps,
+            max_fruits,
+            goal,
             score: 0,
             snake: Snake::default(),
             fruits: vec![],
             board,
+            arena,
+            board_replaced: true,
+            snapshots,
+            recorder,
+            heartbeat,
         }
     }
 
@@ -242,7 +526,7 @@ impl Game {
     }
 }
 
-fn remove_eaten_fruits(fruits: &mut Vec<Fruit>, actual_point: &Point) -> bool {
+pub(super) fn remove_eaten_fruits(fruits: &mut Vec<Fruit>, actual_point: &Point) -> bool {
     let mut removed = false;
     fruits.retain(|fruit| {
         if fruit.point == *actual_point {
@@ -260,6 +544,37 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_counting_missed_ticks() {
+        let scheduled = Instant::now();
+        let period = Duration::from_millis(100);
+
+        assert_eq!(count_missed_ticks(scheduled, scheduled, period), 0);
+        assert_eq!(
+            count_missed_ticks(scheduled, scheduled + Duration::from_millis(99), period),
+            0
+        );
+        assert_eq!(
+            count_missed_ticks(scheduled, scheduled + Duration::from_millis(250), period),
+            2
+        );
+    }
+
+    #[test]
+    fn test_next_tick_follows_schedule_unless_missed() {
+        let scheduled = Instant::now();
+        let period = Duration::from_millis(100);
+
+        assert_eq!(
+            next_tick_after(scheduled, scheduled + Duration::from_millis(2), period),
+            scheduled + period
+        );
+        assert_eq!(
+            next_tick_after(scheduled, scheduled + Duration::from_millis(30), period),
+            scheduled + Duration::from_millis(130)
+        );
+    }
+
     #[test]
     fn test_removing_fruits_on_eat() {
         let mut fruits = vec![Fruit {
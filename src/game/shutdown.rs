@@ -0,0 +1,36 @@
+use std::time::Duration;
+use tokio::signal;
+use tracing::error;
+
+/// Time the whole shutdown may take, whatever is still running afterwards is cut off
+pub const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(10);
+
+/// Resolves once the process is asked to stop, either by Ctrl-C or by SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = signal::ctrl_c().await {
+            error!("Listening for Ctrl-C failed: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                error!("Listening for SIGTERM failed: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
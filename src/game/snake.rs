@@ -1,15 +1,18 @@
+use super::arena::Arena;
 use super::board::get_center_of_board_coordinates;
+use super::consts::SPAWN_LENGTH;
 
 use super::point::*;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 use thiserror::Error;
 use tracing::trace;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct SnakeIncreaseCommand {}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snake {
     body: VecDeque<Point>,
     increase_snake: Option<SnakeIncreaseCommand>,
@@ -21,7 +24,9 @@ impl Default for Snake {
     fn default() -> Self {
         let center = get_center_of_board_coordinates();
 
-        let body: VecDeque<Point> = (0..3).map(|i| Point::new(center.y + i, center.x)).collect();
+        let body: VecDeque<Point> = (0..SPAWN_LENGTH)
+            .map(|i| Point::new(center.y + i, center.x))
+            .collect();
 
         Snake {
             orphaned_tail: body.back().copied().unwrap(),
@@ -37,6 +42,9 @@ pub enum SnakeError {
     #[error("Snake collided with its tail")]
     BitOffHisTail,
 
+    #[error("Snake ran into a wall")]
+    HitWall,
+
     #[error("Snake body is empty")]
     BodyIsEmpty,
 }
@@ -66,19 +74,27 @@ impl Snake {
         new_segment_or_err
     }
 
-    #[tracing::instrument(skip(self))]
-    pub fn make_move(&mut self, direction: Option<Direction>) -> Result<(), SnakeError> {
+    /// Moves the snake one cell through `arena`, the one the game is played in
+    #[tracing::instrument(skip(self, arena))]
+    pub fn make_move(
+        &mut self,
+        arena: &Arena,
+        direction: Option<Direction>,
+    ) -> Result<(), SnakeError> {
         let direction = direction.unwrap_or(self.head_current_direction);
         if direction.opposite() == self.head_current_direction {
             return Ok(());
         }
 
-        let mut new_segment_to_insert = self.prepare_new_segment()?;
+        // Move in specific direction, the snake stays where it is when it runs into a wall.
+        // Going through a portal the body is split between both of its ends, but it never
+        // occupies a portal cell itself, so biting is checked the same way
+        let head = *self.head().ok_or(SnakeError::BodyIsEmpty)?;
+        let new_head = arena.step(head, direction).ok_or(SnakeError::HitWall)?;
 
+        let mut new_segment_to_insert = self.prepare_new_segment()?;
         // Set new head coordinations
-        new_segment_to_insert.set_coords(self.head().ok_or(SnakeError::BodyIsEmpty)?.get_coords());
-        // Move in specific direction
-        new_segment_to_insert += direction;
+        new_segment_to_insert.set_coords(new_head.get_coords());
 
         self.head_current_direction = direction;
 
@@ -125,7 +141,7 @@ impl Snake {
 
 #[cfg(test)]
 mod tests {
-    use super::{get_center_of_board_coordinates, Direction, Point, Snake};
+    use super::{get_center_of_board_coordinates, Arena, Direction, Point, Snake, Topology};
     use crate::game::consts::*;
     use crate::game::snake::SnakeError;
     use pretty_assertions::assert_eq;
@@ -134,19 +150,21 @@ mod tests {
     fn test_snake_making_moves() {
         let moves = [Direction::Right, Direction::Down, Direction::Up];
         let expected = [Ok(()), Ok(()), Ok(())];
+        let arena = Arena::default();
         let mut snake = Snake::default();
 
         moves
             .into_iter()
-            .map(|direction| snake.make_move(Some(direction)))
+            .map(|direction| snake.make_move(&arena, Some(direction)))
             .zip(expected)
             .for_each(|(opt, expected)| assert_eq!(opt, expected));
     }
 
     #[test]
     fn test_snake_moving_without_passed_direction() {
+        let arena = Arena::default();
         let mut snake = Snake::default();
-        let move_result = snake.make_move(None);
+        let move_result = snake.make_move(&arena, None);
         let center = get_center_of_board_coordinates();
 
         let expected_point = Point::new(center.y - 1, center.x);
@@ -157,26 +175,28 @@ mod tests {
 
     #[test]
     fn test_snake_head_positions_while_moving() {
+        let arena = Arena::default();
         let mut snake = Snake::default();
         let center = get_center_of_board_coordinates();
         let mut point = Point::new(center.y, center.x);
 
         assert_eq!(*snake.head().unwrap(), point);
 
-        let _ = snake.make_move(Some(Direction::Right));
+        let _ = snake.make_move(&arena, Some(Direction::Right));
         point.x = center.x + 1;
         assert_eq!(*snake.head().unwrap(), point);
 
-        _ = snake.make_move(Some(Direction::Down));
+        _ = snake.make_move(&arena, Some(Direction::Down));
         point.y = center.y + 1;
         assert_eq!(*snake.head().unwrap(), point);
 
-        _ = snake.make_move(Some(Direction::Up));
+        _ = snake.make_move(&arena, Some(Direction::Up));
         assert_eq!(*snake.head().unwrap(), point);
     }
 
     #[test]
     fn test_snake_head_positions_while_moved_outside_of_bounds() {
+        let arena = Arena::default();
         let mut snake = Snake::default();
         let center = get_center_of_board_coordinates();
 
@@ -185,40 +205,131 @@ mod tests {
             .map(|i| Point::new(center.y, i))
             .for_each(|point| {
                 assert_eq!(*snake.head().unwrap(), point);
-                let _ = snake.make_move(Some(Direction::Right));
+                let _ = snake.make_move(&arena, Some(Direction::Right));
             })
     }
 
     #[test]
     fn test_if_snake_bites_itself_results_in_error() {
+        let arena = Arena::default();
         let mut snake = Snake::default();
 
         // Increase size of the size, so its length is 5. It allows snake to bite itself
         snake.increase_snake_command();
 
-        let mut result = snake.make_move(Some(Direction::Right));
+        let mut result = snake.make_move(&arena, Some(Direction::Right));
         assert_eq!(result.err(), None);
         snake.increase_snake_command();
 
-        result = snake.make_move(Some(Direction::Down));
+        result = snake.make_move(&arena, Some(Direction::Down));
         assert_eq!(result.err(), None);
 
-        result = snake.make_move(Some(Direction::Left));
+        result = snake.make_move(&arena, Some(Direction::Left));
         assert_eq!(result, Err(SnakeError::BitOffHisTail));
     }
 
     #[test]
     fn test_if_snake_size_increasing_command_adds_new_segments() {
+        let arena = Arena::default();
         let mut snake = Snake::default();
 
         assert_eq!(snake.size(), 3);
 
         snake.increase_snake_command();
-        _ = snake.make_move(None);
+        _ = snake.make_move(&arena, None);
         assert_eq!(snake.size(), 4);
 
         snake.increase_snake_command();
-        _ = snake.make_move(None);
+        _ = snake.make_move(&arena, None);
         assert_eq!(snake.size(), 5);
     }
+
+    #[test]
+    fn test_snake_runs_into_wall_of_box() {
+        let arena = Arena::new(Topology::BOX);
+        let mut snake = Snake::default();
+        let center = get_center_of_board_coordinates();
+
+        for _ in 0..center.y {
+            assert_eq!(snake.make_move(&arena, None), Ok(()));
+        }
+        assert_eq!(snake.head().unwrap().y, 0);
+
+        assert_eq!(snake.make_move(&arena, None), Err(SnakeError::HitWall));
+        assert_eq!(snake.head().unwrap().y, 0);
+        assert_eq!(snake.size(), 3);
+    }
+
+    #[test]
+    fn test_snake_comes_back_mirrored_on_klein_bottle() {
+        let arena = Arena::new(Topology::KLEIN_BOTTLE);
+        let mut snake = Snake::default();
+        let center = get_center_of_board_coordinates();
+
+        for _ in center.x..BOARD_SIZE_X {
+            assert_eq!(snake.make_move(&arena, Some(Direction::Right)), Ok(()));
+        }
+
+        assert_eq!(
+            *snake.head().unwrap(),
+            Point::new(BOARD_SIZE_Y - 1 - center.y, 0)
+        );
+        assert_eq!(*snake.get_current_direction(), Direction::Right);
+    }
+
+    #[test]
+    fn test_body_trails_through_both_ends_of_portal() {
+        let center = get_center_of_board_coordinates();
+        let mut arena = Arena::new(Topology::TORUS);
+        arena
+            .add_portals(Point::new(center.y - 2, center.x + 1), Point::new(3, 5))
+            .unwrap();
+        let mut snake = Snake::default();
+
+        assert_eq!(snake.make_move(&arena, Some(Direction::Right)), Ok(()));
+        assert_eq!(snake.make_move(&arena, Some(Direction::Up)), Ok(()));
+        assert_eq!(snake.make_move(&arena, None), Ok(()));
+
+        assert_eq!(
+            snake
+                .get_occupied_points()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![
+                Point::new(2, 5),
+                Point::new(center.y - 1, center.x + 1),
+                Point::new(center.y, center.x + 1)
+            ]
+        );
+        assert_eq!(snake.make_move(&arena, Some(Direction::Left)), Ok(()));
+        assert_eq!(*snake.head().unwrap(), Point::new(2, 4));
+    }
+
+    #[test]
+    fn test_snake_bites_itself_coming_out_of_portal() {
+        // Coming out of the lower end of the portal going up leads right onto the tail
+        let center = get_center_of_board_coordinates();
+        let mut arena = Arena::new(Topology::TORUS);
+        arena
+            .add_portals(
+                Point::new(center.y - 1, center.x + 3),
+                Point::new(center.y + 1, center.x + 1),
+            )
+            .unwrap();
+        let mut snake = Snake::default();
+        for _ in 0..3 {
+            assert_eq!(snake.make_move(&arena, Some(Direction::Right)), Ok(()));
+        }
+        let mut grown = snake.clone();
+
+        assert_eq!(snake.make_move(&arena, Some(Direction::Up)), Ok(()));
+        assert_eq!(*snake.head().unwrap(), Point::new(center.y, center.x + 1));
+
+        grown.increase_snake_command();
+        assert_eq!(
+            grown.make_move(&arena, Some(Direction::Up)),
+            Err(SnakeError::BitOffHisTail)
+        );
+    }
 }
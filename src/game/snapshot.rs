@@ -0,0 +1,260 @@
+use super::board::{Board, CellSymbol};
+use super::hint::{hint_for, Hint};
+use super::point::{Direction, Point};
+use super::state::{change_of, frame_of, BoardState, Delta, Frame};
+
+use arc_swap::ArcSwap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// When the tick after a snapshot is due, letting clients time their moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickTiming {
+    pub next_at: Instant,
+    pub period: Duration,
+}
+
+impl TickTiming {
+    pub fn next_in(&self) -> Duration {
+        self.next_in_at(Instant::now())
+    }
+
+    fn next_in_at(&self, now: Instant) -> Duration {
+        self.next_at.saturating_duration_since(now)
+    }
+}
+
+/// Immutable frame of the game, as it was at the end of a tick
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Increases with every published snapshot, also across games
+    pub tick: u64,
+    pub board: Board,
+    /// Segments of the snake, head first, empty before the game loop has published anything
+    pub snake: Vec<Point>,
+    /// Direction the snake moved in during the tick
+    pub direction: Direction,
+    /// Cells changed since the previous snapshot, `None` when the board was replaced as a whole
+    pub changes: Option<Vec<(Point, CellSymbol)>>,
+    /// `None` before the game loop has published anything
+    pub timing: Option<TickTiming>,
+}
+
+impl Snapshot {
+    pub fn frame(&self) -> Frame {
+        frame_of(&self.board, self.tick)
+    }
+
+    /// Safe moves and the way to the nearest fruit, as seen from this snapshot
+    pub fn hint(&self) -> Hint {
+        let fruits = BoardState::from(&self.board).fruits;
+        hint_for(
+            self.tick,
+            &self.snake,
+            self.direction,
+            &fruits,
+            self.board.arena(),
+        )
+    }
+
+    /// Changes since the previous tick, `None` if the previous frame cannot be patched into this one
+    pub fn delta(&self) -> Option<Delta> {
+        let changes = self.changes.as_ref()?;
+
+        Some(Delta {
+            tick: self.tick,
+            base_tick: self.tick - 1,
+            score: self.board.score(),
+            changes: changes
+                .iter()
+                .map(|(point, symbol)| change_of(point, *symbol))
+                .collect(),
+        })
+    }
+}
+
+/// Latest snapshot of the game, swapped atomically so readers never block the game loop
+/// and never observe a half drawn frame
+#[derive(Debug)]
+pub struct Snapshots {
+    current: ArcSwap<Snapshot>,
+    tick_tx: watch::Sender<u64>,
+    closed: AtomicBool,
+}
+
+impl Snapshots {
+    pub fn load(&self) -> Arc<Snapshot> {
+        self.current.load_full()
+    }
+
+    /// Receiver of tick numbers, lets readers wait for the next snapshot instead of polling
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.tick_tx.subscribe()
+    }
+
+    pub fn publish(
+        &self,
+        board: Board,
+        snake: Vec<Point>,
+        direction: Direction,
+        changes: Option<Vec<(Point, CellSymbol)>>,
+        timing: Option<TickTiming>,
+    ) -> u64 {
+        let tick = self.current.load().tick + 1;
+        self.current.store(Arc::new(Snapshot {
+            tick,
+            board,
+            snake,
+            direction,
+            changes,
+            timing,
+        }));
+        // Nobody waiting for the next tick is not an error
+        let _ = self.tick_tx.send(tick);
+
+        tick
+    }
+
+    /// Marks the last published snapshot as final and wakes up everyone waiting for the next one
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.tick_tx.send_modify(|_| {});
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the snapshots are closed
+    pub async fn closed(&self) {
+        let mut tick_rx = self.subscribe();
+        while !self.is_closed() {
+            if tick_rx.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl Default for Snapshots {
+    fn default() -> Self {
+        let (tick_tx, _) = watch::channel(0);
+
+        Self {
+            current: ArcSwap::from_pointee(Snapshot {
+                tick: 0,
+                board: Board::default(),
+                snake: vec![],
+                direction: Direction::Up,
+                changes: None,
+                timing: None,
+            }),
+            tick_tx,
+            closed: AtomicBool::new(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_published_snapshot_is_loaded_with_next_tick() {
+        let snapshots = Snapshots::default();
+        let mut board = Board::default();
+        board.change_cell_symbol(&Point::new(0, 0), CellSymbol::Fruit);
+
+        let tick = snapshots.publish(board, vec![], Direction::Up, None, None);
+        let snapshot = snapshots.load();
+
+        assert_eq!(tick, 1);
+        assert_eq!(snapshot.tick, 1);
+        assert_eq!(snapshot.board.rows().nth(1).unwrap()[1], CellSymbol::Fruit);
+    }
+
+    #[test]
+    fn test_loaded_snapshot_is_not_affected_by_later_publish() {
+        let snapshots = Snapshots::default();
+        let before = snapshots.load();
+
+        snapshots.publish(Board::default(), vec![], Direction::Up, Some(vec![]), None);
+
+        assert_eq!(before.tick, 0);
+        assert_eq!(snapshots.load().tick, 1);
+    }
+
+    #[test]
+    fn test_delta_applies_onto_previous_frame() {
+        let snapshots = Snapshots::default();
+        let mut previous = snapshots.load().frame();
+        let point = Point::new(3, 4);
+        let mut board = Board::default();
+        board.change_cell_symbol(&point, CellSymbol::SnakeHead);
+
+        snapshots.publish(
+            board,
+            vec![],
+            Direction::Up,
+            Some(vec![(point, CellSymbol::SnakeHead)]),
+            None,
+        );
+        let snapshot = snapshots.load();
+        previous.apply(&snapshot.delta().unwrap()).unwrap();
+
+        assert_eq!(previous, snapshot.frame());
+    }
+
+    #[test]
+    fn test_replaced_board_has_no_delta() {
+        let snapshots = Snapshots::default();
+
+        snapshots.publish(Board::default(), vec![], Direction::Up, None, None);
+
+        assert_eq!(snapshots.load().delta(), None);
+    }
+
+    #[test]
+    fn test_subscribers_are_notified() {
+        let snapshots = Snapshots::default();
+        let mut tick_rx = snapshots.subscribe();
+
+        snapshots.publish(Board::default(), vec![], Direction::Up, Some(vec![]), None);
+
+        assert!(tick_rx.has_changed().unwrap());
+        assert_eq!(*tick_rx.borrow_and_update(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_closing_wakes_up_subscribers() {
+        let snapshots = Arc::new(Snapshots::default());
+        let waiting = tokio::spawn({
+            let snapshots = Arc::clone(&snapshots);
+            async move { snapshots.closed().await }
+        });
+
+        snapshots.publish(Board::default(), vec![], Direction::Up, Some(vec![]), None);
+        snapshots.close();
+
+        waiting.await.unwrap();
+        assert!(snapshots.is_closed());
+    }
+
+    #[test]
+    fn test_next_tick_is_never_in_the_past() {
+        let now = Instant::now();
+        let timing = TickTiming {
+            next_at: now + Duration::from_millis(40),
+            period: Duration::from_millis(100),
+        };
+
+        assert_eq!(timing.next_in_at(now), Duration::from_millis(40));
+        assert_eq!(
+            timing.next_in_at(now + Duration::from_secs(1)),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use super::board::{Board, CellSymbol};
+use super::consts::*;
+use super::point::Point;
+
+pub use snake_proto::{BoardState, Cell, CellChange, Delta, Frame, FrameUpdate, Portal};
+
+/// Cells inside the walls, along with their board points
+fn inner_cells(board: &Board) -> impl Iterator<Item = (Point, CellSymbol)> + '_ {
+    // Skip the walls, so the remaining cells map directly onto board points
+    board
+        .rows()
+        .skip(1)
+        .take(BOARD_SIZE_Y as usize)
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .skip(1)
+                .take(BOARD_SIZE_X as usize)
+                .enumerate()
+                .map(move |(x, cell)| (Point::new(y as u16, x as u16), *cell))
+        })
+}
+
+impl From<CellSymbol> for Cell {
+    fn from(symbol: CellSymbol) -> Self {
+        match symbol {
+            CellSymbol::Snake => Cell::Snake,
+            CellSymbol::SnakeHead => Cell::Head,
+            CellSymbol::Fruit => Cell::Fruit,
+            // Only blocks are ever drawn inside the border
+            CellSymbol::Wall(_) => Cell::Wall,
+            CellSymbol::Portal(pair) => Cell::Portal(pair),
+            CellSymbol::Board | CellSymbol::Junction(_) => Cell::Empty,
+        }
+    }
+}
+
+pub fn frame_of(board: &Board, tick: u64) -> Frame {
+    Frame {
+        tick,
+        width: BOARD_SIZE_X,
+        height: BOARD_SIZE_Y,
+        score: board.score(),
+        cells: inner_cells(board).map(|(_, cell)| cell.into()).collect(),
+    }
+}
+
+pub fn change_of(point: &Point, symbol: CellSymbol) -> CellChange {
+    CellChange {
+        x: point.x,
+        y: point.y,
+        cell: symbol.into(),
+    }
+}
+
+impl From<&Board> for BoardState {
+    fn from(board: &Board) -> Self {
+        let mut state = BoardState {
+            width: BOARD_SIZE_X,
+            height: BOARD_SIZE_Y,
+            topology: board.arena().topology(),
+            score: board.score(),
+            head: None,
+            snake: vec![],
+            fruits: vec![],
+            walls: vec![],
+            portals: board
+                .arena()
+                .portals()
+                .iter()
+                .map(|&(entrance, exit)| Portal { entrance, exit })
+                .collect(),
+            level: board.level().cloned(),
+        };
+
+        for (point, cell) in inner_cells(board) {
+            match cell {
+                CellSymbol::SnakeHead => state.head = Some(point),
+                CellSymbol::Snake => state.snake.push(point),
+                CellSymbol::Fruit => state.fruits.push(point),
+                CellSymbol::Wall(_) => state.walls.push(point),
+                _ => {}
+            }
+        }
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::arena::Arena;
+    use crate::game::point::Topology;
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_frame_of_board() {
+        let mut board = Board::default();
+        board.change_cell_symbol(&Point::new(1, 2), CellSymbol::Fruit);
+
+        let frame = frame_of(&board, 7);
+
+        assert_eq!(frame.tick, 7);
+        assert_eq!(frame.cells.len(), (BOARD_SIZE_X * BOARD_SIZE_Y) as usize);
+        assert_eq!(frame.cell(2, 1), Some(Cell::Fruit));
+        assert_eq!(frame.cell(0, 0), Some(Cell::Empty));
+    }
+
+    #[test]
+    fn test_walls_and_portals_inside_board_are_kept() {
+        let mut arena = Arena::default();
+        arena
+            .add_walls([Point::new(2, 3), Point::new(2, 4)])
+            .unwrap();
+        arena
+            .add_portals(Point::new(15, 5), Point::new(4, 30))
+            .unwrap();
+        let board = Board::new(Arc::new(arena));
+
+        let frame = frame_of(&board, 1);
+        let state = BoardState::from(&board);
+
+        assert_eq!(frame.cell(3, 2), Some(Cell::Wall));
+        assert_eq!(frame.cell(5, 15), Some(Cell::Portal(0)));
+        assert_eq!(frame.cell(30, 4), Some(Cell::Portal(0)));
+        assert_eq!(frame.cell(0, 0), Some(Cell::Empty));
+        assert_eq!(state.walls, vec![Point::new(2, 3), Point::new(2, 4)]);
+        assert_eq!(
+            state.portals,
+            vec![Portal {
+                entrance: Point::new(15, 5),
+                exit: Point::new(4, 30)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_board_state_from_board() {
+        let mut board = Board::default();
+        board.change_cell_symbol(&Point::new(0, 0), CellSymbol::SnakeHead);
+        board.change_cell_symbol(&Point::new(1, 0), CellSymbol::Snake);
+        board.change_cell_symbol(
+            &Point::new(BOARD_SIZE_Y - 1, BOARD_SIZE_X - 1),
+            CellSymbol::Fruit,
+        );
+        board.set_score(3);
+
+        let state = BoardState::from(&board);
+
+        assert_eq!(
+            state,
+            BoardState {
+                width: BOARD_SIZE_X,
+                height: BOARD_SIZE_Y,
+                topology: Topology::TORUS,
+                score: 3,
+                head: Some(Point::new(0, 0)),
+                snake: vec![Point::new(1, 0)],
+                fruits: vec![Point::new(BOARD_SIZE_Y - 1, BOARD_SIZE_X - 1)],
+                walls: vec![],
+                portals: vec![],
+                level: None,
+            }
+        );
+    }
+}
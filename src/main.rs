@@ -1,4 +1,5 @@
-use snake::game::new_game;
+use snake::game::{new_game, ArenaError, ArenaOptions, Campaign, GameMode, Layout, Topology};
+use std::path::PathBuf;
 
 use tracing_subscriber::{
     filter::LevelFilter,
@@ -34,5 +35,82 @@ async fn main() {
     let subscriber = init_tracing();
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
-    new_game(FPS).await;
+    let recording_file = std::env::var_os("SNAKE_RECORDING_FILE").map(PathBuf::from);
+
+    let topology = match std::env::var("SNAKE_TOPOLOGY") {
+        Ok(topology) => match topology.parse::<Topology>() {
+            Ok(topology) => topology,
+            Err(err) => {
+                tracing::error!("Invalid SNAKE_TOPOLOGY: {}", err);
+                std::process::exit(1);
+            }
+        },
+        Err(_) => Topology::default(),
+    };
+
+    let portals = match std::env::var("SNAKE_PORTALS") {
+        Ok(portals) => match portals.trim().parse::<usize>() {
+            Ok(portals) => portals,
+            Err(err) => {
+                tracing::error!("Invalid SNAKE_PORTALS: {}", err);
+                std::process::exit(1);
+            }
+        },
+        Err(_) => 0,
+    };
+
+    let layout = match std::env::var("SNAKE_LAYOUT") {
+        Ok(layout) => match layout.parse::<Layout>() {
+            Ok(layout) => layout,
+            Err(err) => {
+                tracing::error!("Invalid SNAKE_LAYOUT: {}", err);
+                std::process::exit(1);
+            }
+        },
+        Err(_) => Layout::default(),
+    };
+
+    let seed = match std::env::var("SNAKE_SEED") {
+        Ok(seed) => match seed.trim().parse::<u64>() {
+            Ok(seed) => Some(seed),
+            Err(err) => {
+                tracing::error!("Invalid SNAKE_SEED: {}", err);
+                std::process::exit(1);
+            }
+        },
+        Err(_) => None,
+    };
+
+    let arena_options = match ArenaOptions::new(topology, layout, portals, seed) {
+        Ok(arena_options) => arena_options,
+        Err(err @ ArenaError::TooManyPortals(_)) => {
+            tracing::error!("Invalid SNAKE_PORTALS: {}", err);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            tracing::error!("Invalid SNAKE_SEED: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // The campaign lays out its own stages, so the arena options only apply to free games
+    let mode = match std::env::var("SNAKE_MODE").as_deref() {
+        Ok("free") | Err(_) => GameMode::Free(arena_options),
+        Ok("campaign") => match Campaign::new() {
+            Ok(campaign) => GameMode::Campaign(campaign),
+            Err(err) => {
+                tracing::error!("Campaign stage cannot be laid out: {}", err);
+                std::process::exit(1);
+            }
+        },
+        Ok(mode) => {
+            tracing::error!(
+                "Invalid SNAKE_MODE: There are 2 possible modes: [\"free\", \"campaign\"]. `{}` does not match any of them",
+                mode
+            );
+            std::process::exit(1);
+        }
+    };
+
+    new_game(FPS, recording_file, mode).await;
 }
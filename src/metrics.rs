@@ -0,0 +1,117 @@
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Encoder, HistogramVec, IntCounter, IntCounterVec, IntGauge, TextEncoder,
+};
+
+lazy_static! {
+    pub static ref TICKS: IntCounter =
+        register_int_counter!("snake_ticks_total", "Game ticks in which the snake moved").unwrap();
+    pub static ref MISSED_TICKS: IntCounter = register_int_counter!(
+        "snake_missed_ticks_total",
+        "Game ticks which were skipped because the game loop fell behind"
+    )
+    .unwrap();
+    pub static ref COMMANDS_RECEIVED: IntCounter = register_int_counter!(
+        "snake_commands_received_total",
+        "Move commands accepted into the command queue"
+    )
+    .unwrap();
+    pub static ref COMMANDS_REJECTED: IntCounterVec = register_int_counter_vec!(
+        "snake_commands_rejected_total",
+        "Move commands which were rejected, by reason",
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref COMMAND_QUEUE_LENGTH: IntGauge = register_int_gauge!(
+        "snake_command_queue_length",
+        "Move commands waiting in the command queue"
+    )
+    .unwrap();
+    pub static ref COMMAND_QUEUE_CAPACITY: IntGauge = register_int_gauge!(
+        "snake_command_queue_capacity",
+        "Maximum number of move commands the command queue holds"
+    )
+    .unwrap();
+    pub static ref GAMES_PLAYED: IntCounter =
+        register_int_counter!("snake_games_played_total", "Games started").unwrap();
+    pub static ref DEATHS: IntCounterVec = register_int_counter_vec!(
+        "snake_deaths_total",
+        "Games ended by the snake dying, by cause",
+        &["cause"]
+    )
+    .unwrap();
+    pub static ref STAGES_CLEARED: IntCounter = register_int_counter!(
+        "snake_stages_cleared_total",
+        "Games ended by reaching the goal of a campaign stage"
+    )
+    .unwrap();
+    pub static ref SCORE: IntGauge =
+        register_int_gauge!("snake_score", "Score of the current game").unwrap();
+    pub static ref SNAKE_LENGTH: IntGauge =
+        register_int_gauge!("snake_length", "Length of the snake in the current game").unwrap();
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "snake_http_request_duration_seconds",
+        "Latency of HTTP requests, by matched route",
+        &["method", "path", "status"]
+    )
+    .unwrap();
+}
+
+pub mod rejection {
+    pub const INVALID_DIRECTION: &str = "invalid_direction";
+    pub const OPPOSITE_DIRECTION: &str = "opposite_direction";
+    pub const QUEUE_FULL: &str = "queue_full";
+    pub const QUEUE_CLOSED: &str = "queue_closed";
+    pub const RATE_LIMITED: &str = "rate_limited";
+}
+
+pub mod death {
+    pub const BIT_ITSELF: &str = "bit_itself";
+    pub const HIT_WALL: &str = "hit_wall";
+}
+
+/// Registers every metric, statics are otherwise only registered on their first use
+fn initialize() {
+    lazy_static::initialize(&TICKS);
+    lazy_static::initialize(&MISSED_TICKS);
+    lazy_static::initialize(&COMMANDS_RECEIVED);
+    lazy_static::initialize(&COMMANDS_REJECTED);
+    lazy_static::initialize(&COMMAND_QUEUE_LENGTH);
+    lazy_static::initialize(&COMMAND_QUEUE_CAPACITY);
+    lazy_static::initialize(&GAMES_PLAYED);
+    lazy_static::initialize(&DEATHS);
+    lazy_static::initialize(&STAGES_CLEARED);
+    lazy_static::initialize(&SCORE);
+    lazy_static::initialize(&SNAKE_LENGTH);
+    lazy_static::initialize(&HTTP_REQUEST_DURATION);
+}
+
+/// All registered metrics in Prometheus text exposition format
+pub fn gather() -> Result<String, prometheus::Error> {
+    initialize();
+    let mut out = vec![];
+    TextEncoder::new().encode(&prometheus::gather(), &mut out)?;
+
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gather_contains_registered_metrics() {
+        TICKS.inc();
+        COMMANDS_REJECTED
+            .with_label_values(&[rejection::QUEUE_FULL])
+            .inc();
+        DEATHS.with_label_values(&[death::HIT_WALL]).inc();
+
+        let out = gather().unwrap();
+
+        assert!(out.contains("# TYPE snake_ticks_total counter"));
+        assert!(out.contains(r#"snake_deaths_total{cause="hit_wall"}"#));
+        assert!(out.contains(r#"snake_commands_rejected_total{reason="queue_full"}"#));
+    }
+}
@@ -1,5 +1,16 @@
+mod client;
+mod error;
 mod handlers;
 mod health;
+mod metrics;
+mod openapi;
+mod rate_limit;
 mod runner;
+mod telnet;
+mod version;
+mod vote;
 
+pub use openapi::ApiDoc;
+pub use rate_limit::VoteLimiter;
 pub use runner::run;
+pub use telnet::{run_telnet, TELNET_PORT};
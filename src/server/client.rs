@@ -0,0 +1,15 @@
+use actix_web::{get, HttpResponse};
+
+const INDEX_HTML: &str = include_str!("client/index.html");
+
+/// Browser client
+#[utoipa::path(
+    tag = "clients",
+    responses((status = 200, description = "Self-contained HTML page", body = String, content_type = "text/html"))
+)]
+#[get("/")]
+async fn index() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(INDEX_HTML)
+}
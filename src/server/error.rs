@@ -0,0 +1,206 @@
+use super::rate_limit::RateLimited;
+use super::version::API_VERSION;
+use super::vote::MoveRejection;
+
+use crate::game::movement::{OrderError, PlanError};
+use crate::game::Direction;
+use actix_web::{http::header, http::StatusCode, HttpResponse, ResponseError};
+use snake_proto::{error_code, Problem, PROBLEM_CONTENT_TYPE};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Retry hint for a full command queue, which drains within a few ticks
+const QUEUE_FULL_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Errors of the HTTP API, responded with as `Problem`s
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    InvalidDirection(String),
+
+    #[error("{0}")]
+    InvalidCommand(String),
+
+    #[error(
+        "Snake is moving {current}, turning {requested} would reverse it, so the move is ignored"
+    )]
+    ReverseMove {
+        current: Direction,
+        requested: Direction,
+    },
+
+    #[error("{0}")]
+    TickPassed(String),
+
+    #[error(transparent)]
+    RateLimited(#[from] RateLimited),
+
+    #[error(transparent)]
+    Order(#[from] OrderError),
+
+    #[error("{0}")]
+    InvalidQuery(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("API version `{0}` is not supported, the only supported version is {API_VERSION}")]
+    UnsupportedVersion(String),
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidDirection(_) => error_code::INVALID_DIRECTION,
+            Self::InvalidCommand(_) => error_code::INVALID_COMMAND,
+            Self::ReverseMove { .. } => error_code::REVERSE_MOVE_IGNORED,
+            Self::TickPassed(_) => error_code::TICK_PASSED,
+            Self::RateLimited(_) => error_code::RATE_LIMITED,
+            Self::Order(OrderError::QueueFull) => error_code::QUEUE_FULL,
+            Self::Order(OrderError::GameNotRunning) => error_code::GAME_NOT_RUNNING,
+            Self::InvalidQuery(_) => error_code::INVALID_QUERY,
+            Self::NotFound(_) => error_code::NOT_FOUND,
+            Self::UnsupportedVersion(_) => error_code::UNSUPPORTED_VERSION,
+            Self::Internal(_) => error_code::INTERNAL_ERROR,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited(e) => Some(e.retry_after()),
+            Self::Order(OrderError::QueueFull) => Some(QUEUE_FULL_RETRY_AFTER),
+            _ => None,
+        }
+    }
+
+    pub fn problem(&self) -> Problem {
+        let status = self.status_code();
+        let mut problem = Problem::new(
+            status.as_u16(),
+            status.canonical_reason().unwrap_or_default(),
+            self.code(),
+            self.to_string(),
+        );
+        problem.retry_after_ms = self.retry_after().map(|after| after.as_millis() as u64);
+
+        problem
+    }
+}
+
+impl From<MoveRejection> for ApiError {
+    fn from(e: MoveRejection) -> Self {
+        match e {
+            MoveRejection::Reverse { current, requested } => {
+                Self::ReverseMove { current, requested }
+            }
+            MoveRejection::RateLimited(e) => Self::RateLimited(e),
+            MoveRejection::Order(e) => Self::Order(e),
+        }
+    }
+}
+
+impl From<PlanError> for ApiError {
+    fn from(e: PlanError) -> Self {
+        match e {
+            PlanError::Empty
+            | PlanError::TooLong(_)
+            | PlanError::OutOfOrder { .. }
+            | PlanError::TooFarAhead { .. } => Self::InvalidCommand(e.to_string()),
+            PlanError::TickPassed { .. } => Self::TickPassed(e.to_string()),
+            PlanError::NotFound(_) => Self::NotFound(e.to_string()),
+            PlanError::Order(e) => Self::Order(e),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidDirection(_) | Self::InvalidCommand(_) | Self::InvalidQuery(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::ReverseMove { .. } | Self::TickPassed(_) => StatusCode::CONFLICT,
+            Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::Order(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::UnsupportedVersion(_) => StatusCode::NOT_ACCEPTABLE,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        response.content_type(PROBLEM_CONTENT_TYPE);
+        if let Some(after) = self.retry_after() {
+            // The header only takes whole seconds, the body tells the exact time
+            let seconds = after.as_secs_f64().ceil().max(1.0) as u64;
+            response.insert_header((header::RETRY_AFTER, seconds));
+        }
+
+        response.json(self.problem())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_status_codes() {
+        let reverse = ApiError::ReverseMove {
+            current: Direction::Up,
+            requested: Direction::Down,
+        };
+
+        assert_eq!(reverse.status_code(), StatusCode::CONFLICT);
+        assert_eq!(
+            ApiError::Order(OrderError::GameNotRunning).status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            ApiError::InvalidDirection("north".to_string()).status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_problem_carries_code_and_retry_hint() {
+        let error = ApiError::RateLimited(RateLimited::AlreadyVoted {
+            retry_after: Duration::from_millis(100),
+        });
+
+        let problem = error.problem();
+
+        assert_eq!(problem.status, 429);
+        assert_eq!(problem.title, "Too Many Requests");
+        assert_eq!(problem.code, error_code::RATE_LIMITED);
+        assert_eq!(problem.kind, "urn:snake:error:rate_limited");
+        assert_eq!(problem.retry_after_ms, Some(100));
+    }
+
+    #[test]
+    fn test_response_is_problem_json() {
+        let response = ApiError::Order(OrderError::QueueFull).error_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_CONTENT_TYPE
+        );
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "1");
+    }
+
+    #[test]
+    fn test_plan_errors_keep_their_codes() {
+        let passed = ApiError::from(PlanError::TickPassed { tick: 3, next: 5 });
+        let closed = ApiError::from(PlanError::Order(OrderError::GameNotRunning));
+
+        assert_eq!(passed.code(), error_code::TICK_PASSED);
+        assert_eq!(passed.status_code(), StatusCode::CONFLICT);
+        assert_eq!(closed.code(), error_code::GAME_NOT_RUNNING);
+    }
+}
@@ -1,56 +1,813 @@
 use std::sync::RwLock;
 
-use crate::game::{movement::OrderMove, Board, Direction};
-use actix_web::{dev::HttpServiceFactory, error, services, web, Result};
+use super::error::ApiError;
+use super::rate_limit::VoteLimiter;
+use super::vote::vote_move;
+use crate::game::image::{self, ImageOptions, Palette, DEFAULT_CELL_SIZE};
+use crate::game::{
+    movement::OrderMove, BoardState, Direction, FrameUpdate, GameRecorder, RenderStyle, Snapshot,
+    Snapshots,
+};
+use crate::metrics::{self, rejection};
+use actix_web::{
+    error, guard,
+    http::header::{self, HeaderName},
+    web, HttpRequest, HttpResponse, Scope,
+};
+use futures_util::stream;
+use serde::Deserialize;
+use snake_proto::{
+    MovePlan, ServerClock, WireMessage, NEXT_TICK_IN_MS_HEADER, TICK_HEADER, WIRE_CONTENT_TYPE,
+};
+use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::error;
+use utoipa::IntoParams;
 
 pub fn snake_service<T>(
-    board: Arc<RwLock<Board>>,
+    snapshots: Arc<Snapshots>,
     move_manager: Arc<RwLock<T>>,
-) -> impl HttpServiceFactory
+    recorder: Arc<RwLock<GameRecorder>>,
+    limiter: Arc<VoteLimiter>,
+) -> Scope
 where
     T: OrderMove + 'static,
 {
-    services![web::scope("/snake")
+    web::scope("/snake")
+        .app_data(web::Data::new(snapshots))
+        .app_data(web::Data::new(limiter))
+        .app_data(web::Data::new(move_manager))
+        .app_data(
+            web::QueryConfig::default()
+                .error_handler(|e, _| ApiError::InvalidQuery(e.to_string()).into()),
+        )
+        .app_data(
+            web::JsonConfig::default()
+                .error_handler(|e, _| ApiError::InvalidCommand(e.to_string()).into()),
+        )
         .service(
             web::resource("")
-                .app_data(web::Data::new(board))
                 .route(web::get().to(get_game_state))
+                .route(
+                    web::post()
+                        .guard(guard::Header("content-type", WIRE_CONTENT_TYPE))
+                        .to(post_wire_command::<T>),
+                ),
         )
+        .service(web::resource("/events").route(web::get().to(get_game_events)))
+        .service(web::resource("/clock").route(web::get().to(get_clock)))
+        .service(web::resource("/hint").route(web::get().to(get_hint)))
         .service(
-            web::resource("/{direction}")
-                .app_data(web::Data::new(move_manager))
-                .route(
-                    web::post().to::<_, (actix_web::web::Path<String>, web::Data<Arc<RwLock<T>>>)>(
-                        post_direction_command
-                    )
+            web::resource("/replay")
+                .app_data(web::Data::new(recorder))
+                .route(web::get().to(get_replay)),
+        )
+        .service(web::resource("/plans").route(web::post().to(post_plan::<T>)))
+        .service(
+            web::resource("/plans/{id}")
+                .app_data(
+                    web::PathConfig::default()
+                        .error_handler(|e, _| ApiError::NotFound(e.to_string()).into()),
                 )
-        )]
+                .route(web::delete().to(delete_plan::<T>)),
+        )
+        .service(web::resource("/{direction}").route(web::post().to(post_direction_command::<T>)))
 }
 
-async fn get_game_state(board: web::Data<Arc<RwLock<Board>>>) -> Result<String> {
-    let board = board.read().unwrap();
-    let mut out = String::new();
+type Result<T, E = ApiError> = std::result::Result<T, E>;
 
-    board.get_board(&mut out).map_err(|e| {
-        error!("Writing board to str failed: {}", e);
-        error::ErrorInternalServerError(e)
-    })?;
+/// Everything needed to count a move, shared by the text and binary move routes
+type MoveData<T> = (
+    web::Data<Arc<RwLock<T>>>,
+    web::Data<Arc<VoteLimiter>>,
+    web::Data<Arc<Snapshots>>,
+);
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct BoardQuery {
+    /// One of `text` (default), `json`, `svg`, `png`, `binary`
+    format: Option<String>,
+    /// Text renderer, one of `plain` (default), `ansi`, `ascii`, `emoji`
+    style: Option<String>,
+    /// Pixels per cell of images, 1-64, 16 by default
+    cell_size: Option<u32>,
+    /// Colours of images, one of `classic` (default), `dark`, `light`
+    palette: Option<String>,
+}
+
+/// Whether the client asked for the binary wire format through the `Accept` header
+fn accepts_wire(req: &HttpRequest) -> bool {
+    req.headers()
+        .get_all(header::ACCEPT)
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains(WIRE_CONTENT_TYPE))
+}
+
+fn invalid_query(e: impl std::fmt::Display) -> ApiError {
+    ApiError::InvalidQuery(e.to_string())
+}
+
+fn image_options(cell_size: Option<u32>, palette: Option<String>) -> Result<ImageOptions> {
+    let palette = match palette {
+        Some(palette) => Palette::try_from(palette).map_err(invalid_query)?,
+        None => Palette::default(),
+    };
+
+    ImageOptions::new(cell_size.unwrap_or(DEFAULT_CELL_SIZE), palette).map_err(invalid_query)
+}
+
+/// Current board, along with its tick and the time left until the next one in headers
+#[utoipa::path(
+    get,
+    path = "/api/v1/snake",
+    tag = "snake",
+    params(BoardQuery),
+    responses(
+        (status = 200, description = "Board in the requested format", content(
+            ("text/plain" = String),
+            ("application/json" = BoardState),
+            ("image/svg+xml" = String),
+            ("image/png" = Binary),
+            ("application/vnd.snake.v1" = Binary),
+        ), headers(
+            ("tick" = u64, description = "Tick of the board"),
+            ("next-tick-in-ms" = u64, description = "Time left until the next tick resolves, missing before the game has started"),
+        )),
+        (status = 400, description = "Unknown query value", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn get_game_state(
+    req: HttpRequest,
+    query: web::Query<BoardQuery>,
+    snapshots: web::Data<Arc<Snapshots>>,
+) -> Result<HttpResponse> {
+    let query = query.into_inner();
+    let snapshot = snapshots.load();
+    let board = &snapshot.board;
+    let format = query
+        .format
+        .or_else(|| accepts_wire(&req).then(|| "binary".to_string()));
+
+    let mut response = match format.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("text") => {
+            let style = match query.style {
+                Some(style) => RenderStyle::try_from(style).map_err(invalid_query)?,
+                None => RenderStyle::default(),
+            };
+
+            let mut out = String::new();
+
+            board.render(style.renderer(), &mut out).map_err(|e| {
+                error!("Writing board to str failed: {}", e);
+                ApiError::Internal(e.to_string())
+            })?;
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/plain; charset=utf-8")
+                .body(out))
+        }
+        Some("json") => {
+            let state = BoardState::from(board);
+            Ok(HttpResponse::Ok().json(state))
+        }
+        Some("svg") => {
+            let options = image_options(query.cell_size, query.palette)?;
+            let mut out = String::new();
+
+            image::render_svg(board, &options, &mut out).map_err(|e| {
+                error!("Rendering board to SVG failed: {}", e);
+                ApiError::Internal(e.to_string())
+            })?;
+
+            Ok(HttpResponse::Ok().content_type("image/svg+xml").body(out))
+        }
+        Some("png") => {
+            let options = image_options(query.cell_size, query.palette)?;
+            let out = image::render_png(board, &options).map_err(|e| {
+                error!("Rendering board to PNG failed: {}", e);
+                ApiError::Internal(e.to_string())
+            })?;
+
+            Ok(HttpResponse::Ok().content_type("image/png").body(out))
+        }
+        Some("binary") => {
+            let message = WireMessage::State(BoardState::from(board));
+            Ok(HttpResponse::Ok()
+                .content_type(WIRE_CONTENT_TYPE)
+                .body(message.encode()))
+        }
+        Some(format) => Err(ApiError::InvalidQuery(format!(
+            "There are 5 possible formats: ['text', 'json', 'svg', 'png', 'binary']. `{format}` does not match any of them"
+        ))),
+    }?;
+
+    let headers = response.headers_mut();
+    headers.insert(TICK, snapshot.tick.into());
+    if let Some(timing) = snapshot.timing {
+        headers.insert(NEXT_TICK_IN_MS, millis(timing.next_in()).into());
+    }
+
+    Ok(response)
+}
+
+const TICK: HeaderName = HeaderName::from_static(TICK_HEADER);
+const NEXT_TICK_IN_MS: HeaderName = HeaderName::from_static(NEXT_TICK_IN_MS_HEADER);
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// Server time along with the tick timing, so clients can send moves right before a tick
+#[utoipa::path(
+    get,
+    path = "/api/v1/snake/clock",
+    tag = "snake",
+    responses(
+        (status = 200, description = "Server clock", body = ServerClock),
+    )
+)]
+async fn get_clock(snapshots: web::Data<Arc<Snapshots>>) -> HttpResponse {
+    let snapshot = snapshots.load();
+    let server_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .json(ServerClock {
+            server_time_ms: millis(server_time),
+            tick: snapshot.tick,
+            next_tick_in_ms: snapshot.timing.map(|timing| millis(timing.next_in())),
+            tick_period_ms: snapshot.timing.map(|timing| millis(timing.period)),
+        })
+}
+
+/// Directions which are safe to take next and the shortest way to a fruit
+#[utoipa::path(
+    get,
+    path = "/api/v1/snake/hint",
+    tag = "snake",
+    responses(
+        (status = 200, description = "Hint for the next move", body = Hint),
+    )
+)]
+async fn get_hint(snapshots: web::Data<Arc<Snapshots>>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .json(snapshots.load().hint())
+}
+
+/// Maximum number of deltas sent in a row, before a keyframe resynchronizes the client
+const KEYFRAME_INTERVAL: u64 = 50;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct EventsQuery {
+    /// One of `json` (default), `delta`, `binary`
+    encoding: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// Full `BoardState` on every frame
+    Json,
+    /// `FrameUpdate`s, a keyframe followed by deltas against the previously sent frame
+    Delta,
+    /// Same updates as `Delta`, as length prefixed wire messages instead of server-sent events
+    Binary,
+}
+
+impl TryFrom<Option<String>> for Encoding {
+    type Error = ApiError;
+
+    fn try_from(encoding: Option<String>) -> Result<Self> {
+        match encoding.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("json") => Ok(Encoding::Json),
+            Some("delta") => Ok(Encoding::Delta),
+            Some("binary") => Ok(Encoding::Binary),
+            Some(encoding) => Err(ApiError::InvalidQuery(format!(
+                "There are 3 possible encodings: ['json', 'delta', 'binary']. `{encoding}` does not match any of them"
+            ))),
+        }
+    }
+}
+
+/// Picks the update to send, a delta only applies if the client holds the frame right before it
+fn frame_update(snapshot: &Snapshot, last_keyframe: u64, last_sent: Option<u64>) -> FrameUpdate {
+    let delta = snapshot.delta().filter(|delta| {
+        Some(delta.base_tick) == last_sent && snapshot.tick - last_keyframe < KEYFRAME_INTERVAL
+    });
+
+    match delta {
+        Some(delta) => FrameUpdate::Delta(delta),
+        None => FrameUpdate::Keyframe(snapshot.frame()),
+    }
+}
+
+#[derive(Debug)]
+struct EventsStream {
+    snapshots: Arc<Snapshots>,
+    tick_rx: tokio::sync::watch::Receiver<u64>,
+    encoding: Encoding,
+    last_keyframe: u64,
+    last_sent: Option<u64>,
+    closed: bool,
+}
+
+impl EventsStream {
+    fn next_update(&mut self, snapshot: &Snapshot) -> FrameUpdate {
+        let update = frame_update(snapshot, self.last_keyframe, self.last_sent);
+        if let FrameUpdate::Keyframe(_) = update {
+            self.last_keyframe = snapshot.tick;
+        }
+
+        update
+    }
+
+    fn next_event(&mut self) -> serde_json::Result<web::Bytes> {
+        let snapshot = self.snapshots.load();
+
+        let event = match self.encoding {
+            Encoding::Json => {
+                let json = serde_json::to_string(&BoardState::from(&snapshot.board))?;
+                format!("id: {}\ndata: {json}\n\n", snapshot.tick).into()
+            }
+            Encoding::Delta => {
+                let update = self.next_update(&snapshot);
+                let name = match update {
+                    FrameUpdate::Keyframe(_) => "keyframe",
+                    FrameUpdate::Delta(_) => "delta",
+                };
+                let json = serde_json::to_string(&update)?;
+                format!("id: {}\nevent: {name}\ndata: {json}\n\n", snapshot.tick).into()
+            }
+            Encoding::Binary => {
+                let update = self.next_update(&snapshot);
+                WireMessage::Update(update).encode_length_prefixed().into()
+            }
+        };
+        self.last_sent = Some(snapshot.tick);
+
+        Ok(event)
+    }
+
+    /// Final event of the stream, telling the client the server is going away
+    fn shutdown_event(&mut self) -> web::Bytes {
+        self.closed = true;
+        let tick = self.snapshots.load().tick;
+
+        match self.encoding {
+            Encoding::Json | Encoding::Delta => {
+                format!("id: {tick}\nevent: shutdown\ndata: {{\"tick\":{tick}}}\n\n").into()
+            }
+            Encoding::Binary => WireMessage::Shutdown { tick }
+                .encode_length_prefixed()
+                .into(),
+        }
+    }
+}
+
+/// Server-sent events stream, one event per frame, starting with the current one
+///
+/// Frames are sent as `BoardState`s by default, `?encoding=delta` switches to keyframes and deltas.
+/// Binary clients get the same updates as wire messages, without the server-sent events framing
+#[utoipa::path(
+    get,
+    path = "/api/v1/snake/events",
+    tag = "snake",
+    params(EventsQuery),
+    responses(
+        (status = 200, description = "Endless stream of frames", content(
+            ("text/event-stream" = BoardState),
+            ("application/vnd.snake.v1" = Binary),
+        )),
+        (status = 400, description = "Unknown encoding", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn get_game_events(
+    req: HttpRequest,
+    query: web::Query<EventsQuery>,
+    snapshots: web::Data<Arc<Snapshots>>,
+) -> Result<HttpResponse> {
+    let encoding = match query.into_inner().encoding {
+        None if accepts_wire(&req) => Encoding::Binary,
+        encoding => Encoding::try_from(encoding)?,
+    };
+    let content_type = match encoding {
+        Encoding::Binary => WIRE_CONTENT_TYPE,
+        Encoding::Json | Encoding::Delta => "text/event-stream",
+    };
+    let snapshots = Arc::clone(snapshots.get_ref());
+    let tick_rx = snapshots.subscribe();
+    let state = EventsStream {
+        snapshots,
+        tick_rx,
+        encoding,
+        last_keyframe: 0,
+        last_sent: None,
+        closed: false,
+    };
 
-    Ok(out)
+    let events = stream::unfold((state, true), |(mut state, first)| async move {
+        if state.closed {
+            return None;
+        }
+        if !first {
+            // Sender is gone once the game loop has finished
+            state.tick_rx.changed().await.ok()?;
+        }
+        if state.snapshots.is_closed() {
+            let event = state.shutdown_event();
+            return Some((Ok(event), (state, false)));
+        }
+        let event = match state.next_event() {
+            Ok(event) => event,
+            Err(e) => {
+                error!("Serializing frame failed: {}", e);
+                return None;
+            }
+        };
+
+        Some((Ok::<_, error::Error>(event), (state, false)))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events))
+}
+
+/// Longest part of a game a replay shows, every frame of it is encoded on request
+const MAX_REPLAY_SECONDS: u64 = 120;
+/// Largest cell of a replay, bigger ones are scaled down to it
+const MAX_REPLAY_CELL_SIZE: u32 = 16;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ReplayQuery {
+    /// One of `current` (default), `previous`
+    game: Option<String>,
+    /// Only replay the last seconds of the game, at most (and by default) 120
+    seconds: Option<u64>,
+    /// Pixels per cell, 1-16, 16 by default. Sizes up to 64 are accepted and clamped to 16
+    cell_size: Option<u32>,
+    /// One of `classic` (default), `dark`, `light`
+    palette: Option<String>,
 }
 
-async fn post_direction_command(
+/// Animated GIF of a game
+#[utoipa::path(
+    get,
+    path = "/api/v1/snake/replay",
+    tag = "snake",
+    params(ReplayQuery),
+    responses(
+        (status = 200, description = "Replay", body = Binary, content_type = "image/gif"),
+        (status = 400, description = "Unknown query value", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No game has been finished yet", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn get_replay(
+    query: web::Query<ReplayQuery>,
+    recorder: web::Data<Arc<RwLock<GameRecorder>>>,
+) -> Result<HttpResponse> {
+    let query = query.into_inner();
+    let options =
+        image_options(query.cell_size, query.palette)?.with_cell_size_at_most(MAX_REPLAY_CELL_SIZE);
+    let seconds = query.seconds.map_or(MAX_REPLAY_SECONDS, |seconds| {
+        seconds.min(MAX_REPLAY_SECONDS)
+    });
+
+    // Replaying works on a copy, so the game loop is not blocked while encoding
+    let recording = {
+        let recorder = recorder.read().unwrap();
+        match query.game.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("current") => recorder.current().clone(),
+            Some("previous") => recorder
+                .previous()
+                .cloned()
+                .ok_or_else(|| ApiError::NotFound("No game has been finished yet".to_string()))?,
+            Some(game) => {
+                return Err(ApiError::InvalidQuery(format!(
+                    "There are 2 possible games: ['current', 'previous']. `{game}` does not match any of them"
+                )))
+            }
+        }
+    };
+
+    let out =
+        web::block(move || recording.render_gif(Some(Duration::from_secs(seconds)), &options))
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?
+            .map_err(|e| {
+                error!("Rendering replay to GIF failed: {}", e);
+                ApiError::Internal(e.to_string())
+            })?;
+
+    Ok(HttpResponse::Ok().content_type("image/gif").body(out))
+}
+
+fn reject(reason: &str, e: ApiError) -> ApiError {
+    metrics::COMMANDS_REJECTED
+        .with_label_values(&[reason])
+        .inc();
+    e
+}
+
+/// Address the client's limits and plans are kept under
+fn client_of(req: &HttpRequest) -> IpAddr {
+    // Clients without a known address, which only happens in tests, share their limits
+    req.peer_addr()
+        .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |addr| addr.ip())
+}
+
+fn issue_move<T: OrderMove>(
+    req: &HttpRequest,
+    direction: Direction,
+    (move_manager, limiter, snapshots): MoveData<T>,
+) -> Result<&'static str> {
+    let move_manager = move_manager.read().unwrap();
+    vote_move(
+        client_of(req),
+        direction,
+        &snapshots.load(),
+        &*move_manager,
+        &limiter,
+    )?;
+
+    Ok("")
+}
+
+/// Votes for the direction the snake turns to in the next tick
+#[utoipa::path(
+    post,
+    path = "/api/v1/snake/{direction}",
+    tag = "snake",
+    params(("direction" = Direction, Path, description = "Direction to turn to")),
+    responses(
+        (status = 200, description = "Vote was counted"),
+        (status = 400, description = "Unknown direction", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Direction would reverse the snake", body = Problem, content_type = "application/problem+json"),
+        (status = 429, description = "Client already voted in this tick or sent too many requests", body = Problem, content_type = "application/problem+json"),
+        (status = 503, description = "Game does not accept moves", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn post_direction_command<T: OrderMove>(
+    req: HttpRequest,
     path: web::Path<String>,
-    move_manager: web::Data<Arc<RwLock<impl OrderMove>>>,
+    data: MoveData<T>,
+) -> Result<&'static str> {
+    let direction = Direction::try_from(path.into_inner()).map_err(|e| {
+        reject(
+            rejection::INVALID_DIRECTION,
+            ApiError::InvalidDirection(e.to_string()),
+        )
+    })?;
+
+    issue_move(&req, direction, data)
+}
+
+/// Binary counterpart of `post_direction_command`, taking a command wire message as its body
+#[utoipa::path(
+    post,
+    path = "/api/v1/snake",
+    tag = "snake",
+    request_body(content = Binary, content_type = "application/vnd.snake.v1"),
+    responses(
+        (status = 200, description = "Vote was counted"),
+        (status = 400, description = "Body is not a command", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Direction would reverse the snake", body = Problem, content_type = "application/problem+json"),
+        (status = 429, description = "Client already voted in this tick or sent too many requests", body = Problem, content_type = "application/problem+json"),
+        (status = 503, description = "Game does not accept moves", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn post_wire_command<T: OrderMove>(
+    req: HttpRequest,
+    body: web::Bytes,
+    data: MoveData<T>,
 ) -> Result<&'static str> {
-    let direction = Direction::try_from(path.into_inner()).map_err(error::ErrorBadRequest)?;
+    let invalid_command = |detail: String| {
+        reject(
+            rejection::INVALID_DIRECTION,
+            ApiError::InvalidCommand(detail),
+        )
+    };
+    let direction = match WireMessage::decode(&body).map_err(|e| invalid_command(e.to_string()))? {
+        WireMessage::Command(direction) => direction,
+        _ => {
+            return Err(invalid_command(
+                "Only command messages can be posted".to_string(),
+            ))
+        }
+    };
 
-    let move_manager = move_manager.read().unwrap();
+    issue_move(&req, direction, data)
+}
+
+/// Schedules a sequence of moves, made one per tick, replacing the client's previous plan
+///
+/// Every move votes in its tick like a move posted then, moves reversing the snake are ignored.
+/// A planned move takes up the client's vote in its tick, so moves posted then are rejected
+#[utoipa::path(
+    post,
+    path = "/api/v1/snake/plans",
+    tag = "snake",
+    request_body = MovePlan,
+    responses(
+        (status = 202, description = "Plan was scheduled", body = PlanReceipt),
+        (status = 400, description = "Plan is empty, too long, out of order or reaches too far ahead", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "A move refers to a tick which has already passed", body = Problem, content_type = "application/problem+json"),
+        (status = 429, description = "Client sent too many requests, or already voted in the tick of the first move", body = Problem, content_type = "application/problem+json"),
+        (status = 503, description = "Game does not accept moves", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn post_plan<T: OrderMove>(
+    req: HttpRequest,
+    plan: web::Json<MovePlan>,
+    (move_manager, limiter, snapshots): MoveData<T>,
+) -> Result<HttpResponse> {
+    let client = client_of(&req);
+    let tick = snapshots.load().tick;
+    // A first move in the next tick votes along with moves posted during this one
+    let votes_next = plan
+        .moves
+        .first()
+        .is_some_and(|first| first.tick.is_none_or(|planned| planned == tick + 1));
+    match votes_next {
+        true => limiter.try_vote(client, tick),
+        false => limiter.try_request(client),
+    }
+    .map_err(|e| reject(rejection::RATE_LIMITED, e.into()))?;
+
+    let receipt = move_manager
+        .read()
+        .unwrap()
+        .plan_moves(client, &plan.moves, tick);
+    if receipt.is_err() && votes_next {
+        // A rejected plan votes in no tick, so its first move should not take up the vote
+        limiter.refund(client, tick);
+    }
+
+    Ok(HttpResponse::Accepted().json(receipt?))
+}
+
+/// Cancels the moves left in a plan, only the client which scheduled it may do so
+#[utoipa::path(
+    delete,
+    path = "/api/v1/snake/plans/{id}",
+    tag = "snake",
+    params(("id" = u64, Path, description = "Plan to cancel")),
+    responses(
+        (status = 204, description = "Plan was cancelled"),
+        (status = 404, description = "Plan does not exist or has finished", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn delete_plan<T: OrderMove>(
+    req: HttpRequest,
+    id: web::Path<u64>,
+    move_manager: web::Data<Arc<RwLock<T>>>,
+) -> Result<HttpResponse> {
     move_manager
-        .issue_move(direction)
-        .map_err(error::ErrorInternalServerError)?;
+        .read()
+        .unwrap()
+        .cancel_plan(client_of(&req), id.into_inner())?;
 
-    Ok("")
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::movement::OrderError;
+    use crate::game::{Board, CellSymbol, MoveCommandIssuer, Point};
+    use crate::server::rate_limit::RateLimited;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc;
+
+    fn publish_move(snapshots: &Snapshots) {
+        let point = Point::new(1, 1);
+        snapshots.publish(
+            Board::default(),
+            vec![point],
+            Direction::Up,
+            Some(vec![(point, CellSymbol::Board)]),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_consecutive_frames_are_sent_as_deltas() {
+        let snapshots = Snapshots::default();
+        publish_move(&snapshots);
+
+        let update = frame_update(&snapshots.load(), 0, Some(0));
+
+        assert!(matches!(update, FrameUpdate::Delta(_)));
+    }
+
+    #[test]
+    fn test_keyframe_is_sent_after_a_skipped_tick() {
+        let snapshots = Snapshots::default();
+        publish_move(&snapshots);
+        publish_move(&snapshots);
+
+        let update = frame_update(&snapshots.load(), 0, Some(0));
+
+        assert_eq!(update.tick(), 2);
+        assert!(matches!(update, FrameUpdate::Keyframe(_)));
+    }
+
+    #[test]
+    fn test_keyframe_is_sent_periodically() {
+        let snapshots = Snapshots::default();
+        for _ in 0..KEYFRAME_INTERVAL {
+            publish_move(&snapshots);
+        }
+
+        let update = frame_update(&snapshots.load(), 0, Some(KEYFRAME_INTERVAL - 1));
+
+        assert!(matches!(update, FrameUpdate::Keyframe(_)));
+    }
+
+    #[test]
+    fn test_wire_format_is_negotiated_through_accept() {
+        let binary = actix_web::test::TestRequest::default()
+            .insert_header((header::ACCEPT, "text/plain, application/vnd.snake.v1"))
+            .to_http_request();
+        let text = actix_web::test::TestRequest::default()
+            .insert_header((header::ACCEPT, "text/plain"))
+            .to_http_request();
+
+        assert!(accepts_wire(&binary));
+        assert!(!accepts_wire(&text));
+    }
+
+    #[actix_web::test]
+    async fn test_plan_and_posted_move_share_one_vote_per_tick() {
+        let (sender, _receiver) = mpsc::channel(10);
+        let snapshots = Arc::new(Snapshots::default());
+        let data: MoveData<MoveCommandIssuer> = (
+            web::Data::new(Arc::new(RwLock::new(MoveCommandIssuer::from(sender)))),
+            web::Data::new(Arc::new(VoteLimiter::new(10.0))),
+            web::Data::new(Arc::clone(&snapshots)),
+        );
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        let plan = || web::Json(MovePlan::from_iter([Direction::Left, Direction::Up]));
+
+        // Posted first, the move leaves no vote for a plan starting in the next tick
+        issue_move(&req, Direction::Left, data.clone()).unwrap();
+        assert!(matches!(
+            post_plan(req.clone(), plan(), data.clone()).await,
+            Err(ApiError::RateLimited(RateLimited::AlreadyVoted { .. }))
+        ));
+
+        // Planned first, the move takes up the vote of every tick it has a move in
+        publish_move(&snapshots);
+        post_plan(req.clone(), plan(), data.clone()).await.unwrap();
+        assert!(matches!(
+            issue_move(&req, Direction::Right, data.clone()),
+            Err(ApiError::RateLimited(RateLimited::AlreadyVoted { .. }))
+        ));
+        publish_move(&snapshots);
+        assert!(matches!(
+            issue_move(&req, Direction::Right, data.clone()),
+            Err(ApiError::RateLimited(RateLimited::AlreadyVoted { .. }))
+        ));
+        publish_move(&snapshots);
+        assert_eq!(issue_move(&req, Direction::Right, data).unwrap(), "");
+    }
+
+    #[actix_web::test]
+    async fn test_move_left_out_of_full_queue_keeps_the_vote() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let snapshots = Arc::new(Snapshots::default());
+        let data: MoveData<MoveCommandIssuer> = (
+            web::Data::new(Arc::new(RwLock::new(MoveCommandIssuer::from(sender)))),
+            web::Data::new(Arc::new(VoteLimiter::new(10.0))),
+            web::Data::new(Arc::clone(&snapshots)),
+        );
+        let req = actix_web::test::TestRequest::default().to_http_request();
+
+        issue_move(&req, Direction::Left, data.clone()).unwrap();
+        publish_move(&snapshots);
+        assert!(matches!(
+            issue_move(&req, Direction::Left, data.clone()),
+            Err(ApiError::Order(OrderError::QueueFull))
+        ));
+
+        receiver.try_recv().unwrap();
+        assert_eq!(issue_move(&req, Direction::Left, data).unwrap(), "");
+    }
+
+    #[test]
+    fn test_first_frame_is_a_keyframe() {
+        let snapshots = Snapshots::default();
+        publish_move(&snapshots);
+
+        let update = frame_update(&snapshots.load(), 0, None);
+
+        assert!(matches!(update, FrameUpdate::Keyframe(_)));
+    }
 }
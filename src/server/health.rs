@@ -1,6 +1,105 @@
-use actix_web::get;
+use crate::game::{movement::OrderMove, Heartbeat, Liveness, Phase};
 
-#[get("/healthz")]
-async fn healthy() -> String {
-    "Healthy".to_owned()
+use actix_web::{dev::HttpServiceFactory, services, web, HttpResponse};
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use utoipa::ToSchema;
+
+pub fn health_service<T>(
+    heartbeat: Arc<RwLock<Heartbeat>>,
+    move_manager: Arc<RwLock<T>>,
+) -> impl HttpServiceFactory
+where
+    T: OrderMove + 'static,
+{
+    services![
+        web::resource("/healthz")
+            .app_data(web::Data::new(Arc::clone(&heartbeat)))
+            .route(web::get().to(healthy)),
+        web::resource("/readyz")
+            .app_data(web::Data::new(heartbeat))
+            .app_data(web::Data::new(move_manager))
+            .route(web::get().to(ready::<T>))
+    ]
+}
+
+fn describe(liveness: &Liveness) -> String {
+    match liveness {
+        Liveness::Alive => "Healthy".to_owned(),
+        Liveness::Stopped => "Unhealthy: game loop has stopped".to_owned(),
+        Liveness::Stalled { silent_for } => format!(
+            "Unhealthy: game loop has not ticked for {:.1}s",
+            silent_for.as_secs_f32()
+        ),
+    }
+}
+
+/// Liveness, fails once the game loop stops or stalls
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "operations",
+    responses(
+        (status = 200, description = "Game loop is alive", body = String, content_type = "text/plain"),
+        (status = 503, description = "Game loop has stopped or stalled", body = String, content_type = "text/plain"),
+    )
+)]
+async fn healthy(heartbeat: web::Data<Arc<RwLock<Heartbeat>>>) -> HttpResponse {
+    let liveness = heartbeat.read().unwrap().liveness();
+    let body = describe(&liveness);
+
+    match liveness {
+        Liveness::Alive => HttpResponse::Ok().body(body),
+        _ => HttpResponse::ServiceUnavailable().body(body),
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct Readiness {
+    ready: bool,
+    status: String,
+    phase: Phase,
+    last_tick_secs_ago: Option<f32>,
+    command_channel_accepting: bool,
+}
+
+/// Readiness, additionally requires the command channel to accept moves
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "operations",
+    responses(
+        (status = 200, description = "Game accepts moves", body = Readiness),
+        (status = 503, description = "Game does not accept moves", body = Readiness),
+    )
+)]
+async fn ready<T: OrderMove>(
+    heartbeat: web::Data<Arc<RwLock<Heartbeat>>>,
+    move_manager: web::Data<Arc<RwLock<T>>>,
+) -> HttpResponse {
+    let (liveness, phase, last_tick) = {
+        let heartbeat = heartbeat.read().unwrap();
+        (
+            heartbeat.liveness(),
+            heartbeat.phase(),
+            heartbeat.last_tick(),
+        )
+    };
+    let command_channel_accepting = move_manager.read().unwrap().is_accepting();
+
+    let readiness = Readiness {
+        ready: liveness == Liveness::Alive && command_channel_accepting,
+        status: describe(&liveness),
+        phase,
+        last_tick_secs_ago: last_tick
+            .map(|tick| Instant::now().saturating_duration_since(tick).as_secs_f32()),
+        command_channel_accepting,
+    };
+
+    if readiness.ready {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
 }
@@ -0,0 +1,38 @@
+use crate::metrics;
+
+use actix_web::{dev::ServiceResponse, error, get, HttpResponse, Result};
+use std::time::Instant;
+use tracing::error;
+
+/// Prometheus metrics in text exposition format
+#[utoipa::path(
+    tag = "operations",
+    responses((status = 200, description = "Metrics", body = String, content_type = "text/plain"))
+)]
+#[get("/metrics")]
+async fn prometheus_metrics() -> Result<HttpResponse> {
+    let out = metrics::gather().map_err(|e| {
+        error!("Gathering metrics failed: {}", e);
+        error::ErrorInternalServerError(e)
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(out))
+}
+
+/// Records latency of a request under its route pattern, so path parameters do not blow up cardinality
+pub fn observe_latency<B>(res: &ServiceResponse<B>, started: Instant) {
+    let path = res
+        .request()
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_owned());
+
+    metrics::HTTP_REQUEST_DURATION
+        .with_label_values(&[
+            res.request().method().as_str(),
+            &path,
+            res.status().as_str(),
+        ])
+        .observe(started.elapsed().as_secs_f64());
+}
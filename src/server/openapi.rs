@@ -0,0 +1,151 @@
+use super::{client, handlers, health, metrics};
+
+use crate::game::Phase;
+use actix_web::dev::HttpServiceFactory;
+use snake_proto::{
+    BoardState, Cell, CellChange, Delta, Direction, Edge, Frame, FrameUpdate, Goal, Hint, Level,
+    MovePlan, PlanReceipt, PlannedMove, Point, Portal, Problem, ServerClock, Topology,
+};
+use utoipa::{OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+/// Raw bytes, such as images or wire messages
+#[derive(ToSchema)]
+#[schema(value_type = String, format = Binary)]
+pub(super) struct Binary(#[allow(dead_code)] Vec<u8>);
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Snake", description = "Collectively played snake"),
+    paths(
+        handlers::get_game_state,
+        handlers::post_wire_command,
+        handlers::get_game_events,
+        handlers::get_clock,
+        handlers::get_hint,
+        handlers::get_replay,
+        handlers::post_direction_command,
+        handlers::post_plan,
+        handlers::delete_plan,
+        health::healthy,
+        health::ready,
+        metrics::prometheus_metrics,
+        client::index,
+    ),
+    components(schemas(
+        Binary,
+        BoardState,
+        Cell,
+        CellChange,
+        Delta,
+        Direction,
+        Edge,
+        Frame,
+        FrameUpdate,
+        Goal,
+        Hint,
+        Level,
+        MovePlan,
+        Phase,
+        PlanReceipt,
+        PlannedMove,
+        Point,
+        Portal,
+        Problem,
+        ServerClock,
+        Topology,
+        health::Readiness,
+    )),
+    tags(
+        (name = "snake", description = "Watching and steering the game"),
+        (name = "operations", description = "Health checks and monitoring"),
+        (name = "clients", description = "Bundled clients"),
+    )
+)]
+pub struct ApiDoc;
+
+/// OpenAPI document at `/openapi.json`, browsable through Swagger UI at `/docs/`
+pub fn openapi_service() -> impl HttpServiceFactory {
+    SwaggerUi::new("/docs/{_:.*}").url("/openapi.json", ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::rate_limit::VoteLimiter;
+    use super::super::runner::routes;
+    use super::*;
+    use crate::game::{GameRecorder, Heartbeat, MoveCommandIssuer, Snapshots};
+    use actix_web::dev::Service;
+    use actix_web::http::header::{self, HeaderName, HeaderValue};
+    use actix_web::http::Method;
+    use actix_web::{test, App};
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, RwLock};
+    use tokio::sync::mpsc;
+    use utoipa::openapi::PathItemType;
+
+    const ROUTE: HeaderName = HeaderName::from_static("x-route");
+
+    fn method(item_type: &PathItemType) -> Method {
+        match item_type {
+            PathItemType::Get => Method::GET,
+            PathItemType::Post => Method::POST,
+            PathItemType::Put => Method::PUT,
+            PathItemType::Delete => Method::DELETE,
+            PathItemType::Options => Method::OPTIONS,
+            PathItemType::Head => Method::HEAD,
+            PathItemType::Patch => Method::PATCH,
+            PathItemType::Trace => Method::TRACE,
+            PathItemType::Connect => Method::CONNECT,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_documented_operations_match_routes() {
+        let (command_sender, _command_rx) = mpsc::channel(1);
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    let res = srv.call(req);
+                    async move {
+                        let mut res = res.await?;
+                        let pattern = res.request().match_pattern().unwrap_or_default();
+                        res.headers_mut()
+                            .insert(ROUTE, HeaderValue::from_str(&pattern).unwrap());
+                        Ok(res)
+                    }
+                })
+                .configure(routes(
+                    Arc::new(RwLock::new(MoveCommandIssuer::from(command_sender))),
+                    Arc::new(Snapshots::default()),
+                    Arc::new(RwLock::new(GameRecorder::default())),
+                    Arc::new(RwLock::new(Heartbeat::new(10.0))),
+                    Arc::new(VoteLimiter::new(10.0)),
+                )),
+        )
+        .await;
+
+        let spec = ApiDoc::openapi();
+        for (path, item) in &spec.paths.paths {
+            for item_type in item.operations.keys() {
+                let method = method(item_type);
+                let req = test::TestRequest::default()
+                    .method(method.clone())
+                    .uri(&path.replace("{direction}", "left").replace("{id}", "1"))
+                    // Binary routes are only matched with the wire content type
+                    .insert_header((header::CONTENT_TYPE, snake_proto::WIRE_CONTENT_TYPE))
+                    .to_request();
+
+                let res = test::call_service(&app, req).await;
+
+                assert_eq!(
+                    res.headers()
+                        .get(ROUTE)
+                        .and_then(|route| route.to_str().ok()),
+                    Some(path.as_str()),
+                    "{method} {path} is documented, but not routed"
+                );
+            }
+        }
+    }
+}
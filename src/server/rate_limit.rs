@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Move requests a single client may send per `WINDOW`, whether they end up counted or not
+const MAX_REQUESTS_PER_WINDOW: u32 = 20;
+const WINDOW: Duration = Duration::from_secs(1);
+/// Clients tracked at once, idle ones are forgotten first and the least recently active next
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RateLimited {
+    #[error("Only one vote per tick is counted, retry in {}ms", .retry_after.as_millis())]
+    AlreadyVoted { retry_after: Duration },
+
+    #[error("Too many move requests, retry in {}ms", .retry_after.as_millis())]
+    TooManyRequests { retry_after: Duration },
+}
+
+impl RateLimited {
+    pub fn retry_after(&self) -> Duration {
+        match self {
+            Self::AlreadyVoted { retry_after } | Self::TooManyRequests { retry_after } => {
+                *retry_after
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Voter {
+    window_start: Instant,
+    requests: u32,
+    voted_in_tick: Option<u64>,
+}
+
+/// Keeps a single client from deciding where the snake goes by sending moves faster than others,
+/// every client gets one vote per tick and a bounded number of requests per second
+#[derive(Debug)]
+pub struct VoteLimiter {
+    /// Speed changes between games of the campaign
+    tick_period: Mutex<Duration>,
+    voters: Mutex<HashMap<IpAddr, Voter>>,
+}
+
+impl VoteLimiter {
+    pub fn new(fps: f32) -> Self {
+        Self {
+            tick_period: Mutex::new(Duration::from_secs_f32(1.0 / fps)),
+            voters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Follows the speed of a new game, so rejected votes are retried after its ticks
+    pub fn set_fps(&self, fps: f32) {
+        *self.tick_period.lock().unwrap() = Duration::from_secs_f32(1.0 / fps);
+    }
+
+    fn tick_period(&self) -> Duration {
+        *self.tick_period.lock().unwrap()
+    }
+
+    /// Counts a vote of `client` in `tick`, unless it already voted in it or sends too many requests
+    pub fn try_vote(&self, client: IpAddr, tick: u64) -> Result<(), RateLimited> {
+        self.try_vote_at(client, tick, Instant::now())
+    }
+
+    /// Rejection of a vote in a tick the client's plan already votes in
+    pub fn voted_by_plan(&self) -> RateLimited {
+        RateLimited::AlreadyVoted {
+            retry_after: self.tick_period(),
+        }
+    }
+
+    /// Gives back the vote of `client` in `tick`, for a move which was not issued after all
+    pub fn refund(&self, client: IpAddr, tick: u64) {
+        if let Some(voter) = self.voters.lock().unwrap().get_mut(&client) {
+            if voter.voted_in_tick == Some(tick) {
+                voter.voted_in_tick = None;
+            }
+        }
+    }
+
+    /// Counts a request of `client` which does not vote right away, such as a plan of moves
+    pub fn try_request(&self, client: IpAddr) -> Result<(), RateLimited> {
+        self.try_request_at(client, None, Instant::now())
+    }
+
+    fn try_vote_at(&self, client: IpAddr, tick: u64, now: Instant) -> Result<(), RateLimited> {
+        self.try_request_at(client, Some(tick), now)
+    }
+
+    fn try_request_at(
+        &self,
+        client: IpAddr,
+        vote_in: Option<u64>,
+        now: Instant,
+    ) -> Result<(), RateLimited> {
+        let mut voters = self.voters.lock().unwrap();
+        if voters.len() >= MAX_TRACKED_CLIENTS && !voters.contains_key(&client) {
+            voters.retain(|_, voter| now.saturating_duration_since(voter.window_start) < WINDOW);
+        }
+        // Every tracked client is active, so the one whose window started first makes room
+        if voters.len() >= MAX_TRACKED_CLIENTS && !voters.contains_key(&client) {
+            let oldest = voters
+                .iter()
+                .min_by_key(|(_, voter)| voter.window_start)
+                .map(|(client, _)| *client);
+            if let Some(oldest) = oldest {
+                voters.remove(&oldest);
+            }
+        }
+
+        let voter = voters.entry(client).or_insert(Voter {
+            window_start: now,
+            requests: 0,
+            voted_in_tick: None,
+        });
+
+        let elapsed = now.saturating_duration_since(voter.window_start);
+        if elapsed >= WINDOW {
+            voter.window_start = now;
+            voter.requests = 0;
+        }
+        voter.requests += 1;
+        if voter.requests > MAX_REQUESTS_PER_WINDOW {
+            return Err(RateLimited::TooManyRequests {
+                retry_after: WINDOW.saturating_sub(elapsed),
+            });
+        }
+
+        let Some(tick) = vote_in else {
+            return Ok(());
+        };
+        if voter.voted_in_tick == Some(tick) {
+            return Err(RateLimited::AlreadyVoted {
+                retry_after: self.tick_period(),
+            });
+        }
+        voter.voted_in_tick = Some(tick);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::net::Ipv4Addr;
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    const OTHER_CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+    #[test]
+    fn test_one_vote_per_tick() {
+        let limiter = VoteLimiter::new(10.0);
+        let now = Instant::now();
+
+        assert_eq!(limiter.try_vote_at(CLIENT, 1, now), Ok(()));
+        assert_eq!(
+            limiter.try_vote_at(CLIENT, 1, now),
+            Err(RateLimited::AlreadyVoted {
+                retry_after: limiter.tick_period()
+            })
+        );
+        assert_eq!(limiter.try_vote_at(OTHER_CLIENT, 1, now), Ok(()));
+        assert_eq!(limiter.try_vote_at(CLIENT, 2, now), Ok(()));
+    }
+
+    #[test]
+    fn test_refunded_vote_can_be_cast_again() {
+        let limiter = VoteLimiter::new(10.0);
+        let now = Instant::now();
+
+        assert_eq!(limiter.try_vote_at(CLIENT, 1, now), Ok(()));
+        limiter.refund(CLIENT, 1);
+        assert_eq!(limiter.try_vote_at(CLIENT, 1, now), Ok(()));
+        limiter.refund(CLIENT, 0);
+        assert!(limiter.try_vote_at(CLIENT, 1, now).is_err());
+    }
+
+    #[test]
+    fn test_tracked_clients_are_capped() {
+        let limiter = VoteLimiter::new(10.0);
+        let now = Instant::now();
+        let client = |i: u32| IpAddr::V4(Ipv4Addr::from(i));
+
+        for i in 0..MAX_TRACKED_CLIENTS as u32 {
+            let at = now + Duration::from_micros(i.into());
+            assert_eq!(limiter.try_vote_at(client(i), 1, at), Ok(()));
+        }
+        assert_eq!(limiter.try_vote_at(CLIENT, 1, now + WINDOW / 2), Ok(()));
+
+        let voters = limiter.voters.lock().unwrap();
+        assert_eq!(voters.len(), MAX_TRACKED_CLIENTS);
+        assert!(!voters.contains_key(&client(0)));
+        assert!(voters.contains_key(&client(1)));
+    }
+
+    #[test]
+    fn test_retry_follows_speed_of_game() {
+        let limiter = VoteLimiter::new(10.0);
+        limiter.set_fps(4.0);
+
+        assert_eq!(
+            limiter.voted_by_plan().retry_after(),
+            Duration::from_millis(250)
+        );
+    }
+
+    #[test]
+    fn test_requests_are_limited_per_window() {
+        let limiter = VoteLimiter::new(10.0);
+        let now = Instant::now();
+
+        for tick in 0..MAX_REQUESTS_PER_WINDOW {
+            assert_eq!(limiter.try_vote_at(CLIENT, tick as u64, now), Ok(()));
+        }
+        let later = now + Duration::from_millis(400);
+        assert_eq!(
+            limiter.try_vote_at(CLIENT, 100, later),
+            Err(RateLimited::TooManyRequests {
+                retry_after: Duration::from_millis(600)
+            })
+        );
+        assert_eq!(limiter.try_vote_at(CLIENT, 101, now + WINDOW), Ok(()));
+    }
+
+    #[test]
+    fn test_requests_without_vote_share_the_window() {
+        let limiter = VoteLimiter::new(10.0);
+        let now = Instant::now();
+
+        assert_eq!(limiter.try_request_at(CLIENT, None, now), Ok(()));
+        assert_eq!(limiter.try_vote_at(CLIENT, 1, now), Ok(()));
+        for _ in 2..MAX_REQUESTS_PER_WINDOW {
+            assert_eq!(limiter.try_request_at(CLIENT, None, now), Ok(()));
+        }
+        assert!(limiter.try_request_at(CLIENT, None, now).is_err());
+    }
+}
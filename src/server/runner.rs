@@ -1,29 +1,96 @@
+use super::client::index;
 use super::handlers::snake_service;
-use super::health::healthy;
+use super::health::health_service;
+use super::metrics::{observe_latency, prometheus_metrics};
+use super::openapi::openapi_service;
+use super::rate_limit::VoteLimiter;
+use super::version::{deprecate, negotiate_version, API_PREFIX};
 
 use crate::game::movement::OrderMove;
-use crate::game::Board;
-use actix_web::{App, HttpServer};
+use crate::game::{GameRecorder, Heartbeat, Snapshots};
+use actix_web::dev::{Server, Service};
+use actix_web::{web, App, HttpServer};
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
 use tracing::info;
 use tracing_actix_web::TracingLogger;
 
-const HOST: &str = "0.0.0.0";
+pub(super) const HOST: &str = "0.0.0.0";
 const PORT: u16 = 8080;
+/// Time requests in flight get to finish once the server is stopped
+const SHUTDOWN_TIMEOUT_SECS: u64 = 5;
 
-pub async fn run<T>(move_manager: Arc<RwLock<T>>, board: Arc<RwLock<Board>>) -> std::io::Result<()>
+/// Every route of the server, without any middleware
+pub(super) fn routes<T>(
+    move_manager: Arc<RwLock<T>>,
+    snapshots: Arc<Snapshots>,
+    recorder: Arc<RwLock<GameRecorder>>,
+    heartbeat: Arc<RwLock<Heartbeat>>,
+    limiter: Arc<VoteLimiter>,
+) -> impl FnOnce(&mut web::ServiceConfig)
+where
+    T: OrderMove + 'static,
+{
+    move |cfg| {
+        cfg.service(
+            web::scope(API_PREFIX)
+                .wrap_fn(negotiate_version)
+                .service(snake_service(
+                    Arc::clone(&snapshots),
+                    Arc::clone(&move_manager),
+                    Arc::clone(&recorder),
+                    Arc::clone(&limiter),
+                )),
+        )
+        // Unversioned routes from before `/api/v1`, kept for existing clients
+        .service(
+            snake_service(snapshots, Arc::clone(&move_manager), recorder, limiter)
+                .wrap_fn(deprecate),
+        )
+        .service(health_service(heartbeat, move_manager))
+        .service(prometheus_metrics)
+        .service(index)
+        .service(openapi_service());
+    }
+}
+
+pub fn run<T>(
+    move_manager: Arc<RwLock<T>>,
+    snapshots: Arc<Snapshots>,
+    recorder: Arc<RwLock<GameRecorder>>,
+    heartbeat: Arc<RwLock<Heartbeat>>,
+    limiter: Arc<VoteLimiter>,
+) -> std::io::Result<Server>
 where
     T: OrderMove + 'static,
 {
     info!("Starting web server on {}:{}", HOST, PORT);
 
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
             .wrap(TracingLogger::default())
-            .service(snake_service(Arc::clone(&board), Arc::clone(&move_manager)))
-            .service(healthy)
+            .wrap_fn(|req, srv| {
+                let started = Instant::now();
+                let res = srv.call(req);
+                async move {
+                    let res = res.await?;
+                    observe_latency(&res, started);
+                    Ok(res)
+                }
+            })
+            .configure(routes(
+                Arc::clone(&move_manager),
+                Arc::clone(&snapshots),
+                Arc::clone(&recorder),
+                Arc::clone(&heartbeat),
+                Arc::clone(&limiter),
+            ))
     })
     .bind((HOST, PORT))?
-    .run()
-    .await
+    // Shutdown is coordinated by the game runner, which ends the streams before stopping
+    .disable_signals()
+    .shutdown_timeout(SHUTDOWN_TIMEOUT_SECS)
+    .run();
+
+    Ok(server)
 }
@@ -0,0 +1,331 @@
+use super::rate_limit::VoteLimiter;
+use super::runner::HOST;
+use super::vote::vote_move;
+
+use crate::game::movement::OrderMove;
+use crate::game::{Board, CellSymbol, Direction, RenderStyle, Renderer, Snapshots};
+use std::fmt::{self, Write};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, Duration};
+use tracing::{debug, error, info, warn};
+
+pub const TELNET_PORT: u16 = 2323;
+/// Pause after a failed accept, which tends to fail again right away when out of file descriptors
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// Telnet commands
+const IAC: u8 = 255;
+const WILL: u8 = 251;
+const DONT: u8 = 254;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+
+/// Switches the client into character mode without local echo
+const NEGOTIATION: &[u8] = &[IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD];
+/// Hides the cursor and clears the screen
+const SETUP: &[u8] = b"\x1b[?25l\x1b[2J";
+const TEARDOWN: &[u8] = b"\x1b[0m\x1b[?25h\r\n";
+const HOME: &str = "\x1b[H";
+const HELP: &str = "Arrows / hjkl / wasd to steer, q to quit";
+const GOODBYE: &[u8] = b"Server is shutting down, bye!\r\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Move(Direction),
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum DecoderState {
+    #[default]
+    Ground,
+    Command,
+    CommandOption,
+    Subnegotiation,
+    SubnegotiationCommand,
+    Escape,
+    ControlSequence,
+}
+
+/// Turns the raw bytes sent by a telnet client into keys, skipping telnet negotiation
+#[derive(Debug, Default)]
+struct KeyDecoder {
+    state: DecoderState,
+}
+
+impl KeyDecoder {
+    fn feed(&mut self, byte: u8) -> Option<Key> {
+        use DecoderState::*;
+
+        let (state, key) = match (self.state, byte) {
+            (Ground, IAC) => (Command, None),
+            (Ground, 0x1b) => (Escape, None),
+            // Ctrl-C and Ctrl-D arrive as plain bytes in character mode
+            (Ground, b'q' | b'Q' | 0x03 | 0x04) => (Ground, Some(Key::Quit)),
+            (Ground, byte) => (Ground, key_direction(byte).map(Key::Move)),
+            (Command, WILL..=DONT) => (CommandOption, None),
+            (Command, SB) => (Subnegotiation, None),
+            (Command, _) | (CommandOption, _) => (Ground, None),
+            (Subnegotiation, IAC) => (SubnegotiationCommand, None),
+            (Subnegotiation, _) => (Subnegotiation, None),
+            (SubnegotiationCommand, SE) => (Ground, None),
+            (SubnegotiationCommand, _) => (Subnegotiation, None),
+            (Escape, b'[' | b'O') => (ControlSequence, None),
+            (Escape, _) => (Ground, None),
+            (ControlSequence, byte) => (Ground, arrow_direction(byte).map(Key::Move)),
+        };
+        self.state = state;
+
+        key
+    }
+}
+
+fn key_direction(byte: u8) -> Option<Direction> {
+    match byte.to_ascii_lowercase() {
+        b'k' | b'w' => Some(Direction::Up),
+        b'j' | b's' => Some(Direction::Down),
+        b'h' | b'a' => Some(Direction::Left),
+        b'l' | b'd' => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn arrow_direction(byte: u8) -> Option<Direction> {
+    match byte {
+        b'A' => Some(Direction::Up),
+        b'B' => Some(Direction::Down),
+        b'C' => Some(Direction::Right),
+        b'D' => Some(Direction::Left),
+        _ => None,
+    }
+}
+
+/// ANSI colours with the CRLF line endings telnet clients expect
+struct TelnetRenderer;
+
+impl Renderer for TelnetRenderer {
+    fn write_cell(&self, wr: &mut dyn Write, cell: CellSymbol) -> fmt::Result {
+        RenderStyle::Ansi.renderer().write_cell(wr, cell)
+    }
+
+    fn write_line_end(&self, wr: &mut dyn Write) -> fmt::Result {
+        wr.write_str("\r\n")
+    }
+}
+
+/// Redraws the whole screen in place, starting from the top left corner
+fn draw_frame(board: &Board) -> Result<String, fmt::Error> {
+    let mut out = String::from(HOME);
+    board.render(&TelnetRenderer, &mut out)?;
+    write!(out, "Score: {}\r\n{HELP}\r\n", board.score())?;
+
+    Ok(out)
+}
+
+async fn serve_player(
+    stream: TcpStream,
+    peer: SocketAddr,
+    snapshots: Arc<Snapshots>,
+    move_manager: Arc<RwLock<impl OrderMove>>,
+    limiter: Arc<VoteLimiter>,
+) -> io::Result<()> {
+    let (mut reader, mut writer) = stream.into_split();
+    let mut tick_rx = snapshots.subscribe();
+    let mut keys = KeyDecoder::default();
+    let mut buf = [0; 64];
+
+    writer.write_all(NEGOTIATION).await?;
+    writer.write_all(SETUP).await?;
+    let mut redraw = true;
+    loop {
+        if redraw {
+            let frame = draw_frame(&snapshots.load().board).map_err(io::Error::other)?;
+            writer.write_all(frame.as_bytes()).await?;
+        }
+
+        tokio::select! {
+            changed = tick_rx.changed() => {
+                // Sender is gone once the game loop has finished
+                if changed.is_err() {
+                    break;
+                }
+                if snapshots.is_closed() {
+                    writer.write_all(TEARDOWN).await?;
+                    return writer.write_all(GOODBYE).await;
+                }
+                redraw = true;
+            }
+            read = reader.read(&mut buf) => {
+                let read = read?;
+                if read == 0 {
+                    return Ok(());
+                }
+                redraw = false;
+
+                let mut quit = false;
+                for key in buf[..read].iter().filter_map(|byte| keys.feed(*byte)) {
+                    match key {
+                        Key::Move(direction) => {
+                            let move_manager = move_manager.read().unwrap();
+                            // Held down keys repeat far quicker than the snake moves, the vote
+                            // keeps them to one move per tick
+                            let voted = vote_move(
+                                peer.ip(),
+                                direction,
+                                &snapshots.load(),
+                                &*move_manager,
+                                &limiter,
+                            );
+                            if let Err(e) = voted {
+                                debug!("Telnet move was not counted: {}", e);
+                            }
+                        }
+                        Key::Quit => quit = true,
+                    }
+                }
+                if quit {
+                    break;
+                }
+            }
+        }
+    }
+
+    writer.write_all(TEARDOWN).await
+}
+
+/// Accepts telnet players, who watch the board and steer the same snake as HTTP clients.
+/// Telnet is optional, so failing to listen or to accept a player never stops the game
+pub async fn run_telnet<T>(
+    port: u16,
+    move_manager: Arc<RwLock<T>>,
+    snapshots: Arc<Snapshots>,
+    limiter: Arc<VoteLimiter>,
+) where
+    T: OrderMove + 'static,
+{
+    let listener = match TcpListener::bind((HOST, port)).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(
+                "Telnet server failed to start on {}:{}, carrying on without it: {}",
+                HOST, port, err
+            );
+            return;
+        }
+    };
+    info!("Starting telnet server on {}:{}", HOST, port);
+
+    let mut players = JoinSet::new();
+    let closed = snapshots.closed();
+    tokio::pin!(closed);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        warn!("Accepting a telnet player failed: {}", err);
+                        sleep(ACCEPT_RETRY_DELAY).await;
+                        continue;
+                    }
+                };
+                debug!("Telnet player connected from {}", peer);
+
+                let snapshots = Arc::clone(&snapshots);
+                let move_manager = Arc::clone(&move_manager);
+                let limiter = Arc::clone(&limiter);
+                players.spawn(async move {
+                    if let Err(e) = serve_player(stream, peer, snapshots, move_manager, limiter).await {
+                        debug!("Telnet player {} disconnected: {}", peer, e);
+                    }
+                });
+            }
+            // Reaps players who have left
+            Some(_) = players.join_next() => {}
+            _ = &mut closed => break,
+        }
+    }
+
+    // Players are told about the shutdown by the closed snapshots themselves
+    while players.join_next().await.is_some() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::MoveCommandIssuer;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc;
+
+    fn decode(bytes: &[u8]) -> Vec<Key> {
+        let mut keys = KeyDecoder::default();
+        bytes.iter().filter_map(|byte| keys.feed(*byte)).collect()
+    }
+
+    #[test]
+    fn test_decoding_letters() {
+        assert_eq!(
+            decode(b"hjklW"),
+            vec![
+                Key::Move(Direction::Left),
+                Key::Move(Direction::Down),
+                Key::Move(Direction::Up),
+                Key::Move(Direction::Right),
+                Key::Move(Direction::Up),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decoding_arrows() {
+        assert_eq!(
+            decode(b"\x1b[A\x1b[D\x1bOB"),
+            vec![
+                Key::Move(Direction::Up),
+                Key::Move(Direction::Left),
+                Key::Move(Direction::Down),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_negotiation_is_skipped() {
+        let mut bytes = vec![IAC, 253, ECHO, IAC, SB, 24, 0, b'h', IAC, SE];
+        bytes.extend_from_slice(b"\r\0q");
+
+        assert_eq!(decode(&bytes), vec![Key::Quit]);
+    }
+
+    #[tokio::test]
+    async fn test_taken_port_does_not_fail() {
+        let taken = TcpListener::bind((HOST, 0)).await.unwrap();
+        let (sender, _receiver) = mpsc::channel(1);
+
+        let running = run_telnet(
+            taken.local_addr().unwrap().port(),
+            Arc::new(RwLock::new(MoveCommandIssuer::from(sender))),
+            Arc::new(Snapshots::default()),
+            Arc::new(VoteLimiter::new(10.0)),
+        );
+
+        tokio::time::timeout(Duration::from_secs(1), running)
+            .await
+            .expect("telnet server gave up right away");
+    }
+
+    #[test]
+    fn test_frame_uses_crlf_line_endings() {
+        let frame = draw_frame(&Board::default()).unwrap();
+
+        assert!(frame.starts_with(HOME));
+        assert!(!frame.replace("\r\n", "").contains('\n'));
+        assert!(frame.contains("Score: 0\r\n"));
+    }
+}
@@ -0,0 +1,152 @@
+use super::error::ApiError;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use actix_web::Error;
+use std::future::Future;
+
+/// Current, and so far only, version of the game API
+pub const API_VERSION: u32 = 1;
+/// Prefix of every route of the current API version
+pub const API_PREFIX: &str = "/api/v1";
+
+/// Lets clients pin the version they were written against, responses always carry it
+const API_VERSION_HEADER: HeaderName = HeaderName::from_static("api-version");
+const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+
+/// Version asked for through the `Api-Version` header, requests without it get the current one
+fn check_version(headers: &HeaderMap) -> Result<(), ApiError> {
+    let Some(requested) = headers.get(API_VERSION_HEADER) else {
+        return Ok(());
+    };
+
+    match requested.to_str().map(str::trim) {
+        Ok(version) if version.parse() == Ok(API_VERSION) => Ok(()),
+        _ => Err(ApiError::UnsupportedVersion(
+            String::from_utf8_lossy(requested.as_bytes()).into_owned(),
+        )),
+    }
+}
+
+/// Middleware of versioned routes, rejecting requests for other versions
+pub fn negotiate_version<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<EitherBody<B>>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    let res = match check_version(req.headers()) {
+        Ok(()) => Ok(srv.call(req)),
+        Err(e) => Err(req.error_response(e)),
+    };
+
+    async move {
+        let mut res = match res {
+            Ok(res) => res.await?.map_into_left_body(),
+            Err(res) => res.map_into_right_body(),
+        };
+        res.headers_mut()
+            .insert(API_VERSION_HEADER, HeaderValue::from(API_VERSION));
+
+        Ok(res)
+    }
+}
+
+/// Middleware of the unversioned routes, which point to their versioned successors
+pub fn deprecate<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    let successor = format!("<{API_PREFIX}{}>; rel=\"successor-version\"", req.path());
+    let res = srv.call(req);
+
+    async move {
+        let mut res = res.await?;
+        let headers = res.headers_mut();
+        headers.insert(DEPRECATION_HEADER, HeaderValue::from_static("true"));
+        if let Ok(successor) = HeaderValue::from_str(&successor) {
+            headers.insert(header::LINK, successor);
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+    use pretty_assertions::assert_eq;
+
+    fn headers(version: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(API_VERSION_HEADER, HeaderValue::from_static(version));
+        headers
+    }
+
+    #[test]
+    fn test_missing_version_means_current() {
+        assert!(check_version(&HeaderMap::new()).is_ok());
+    }
+
+    #[test]
+    fn test_current_version_is_accepted() {
+        assert!(check_version(&headers("1")).is_ok());
+        assert!(check_version(&headers(" 1 ")).is_ok());
+    }
+
+    #[test]
+    fn test_other_versions_are_rejected() {
+        let error = check_version(&headers("2")).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "API version `2` is not supported, the only supported version is 1"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_versioned_and_legacy_routes() {
+        let app = init_service(
+            App::new()
+                .service(
+                    web::scope(API_PREFIX)
+                        .wrap_fn(negotiate_version)
+                        .route("/snake", web::get().to(HttpResponse::Ok)),
+                )
+                .service(
+                    web::scope("/snake")
+                        .wrap_fn(deprecate)
+                        .route("", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        let res = call_service(&app, TestRequest::get().uri("/api/v1/snake").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(API_VERSION_HEADER).unwrap(), "1");
+
+        let req = TestRequest::get()
+            .uri("/api/v1/snake")
+            .insert_header((API_VERSION_HEADER, "2"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(res.headers().get(API_VERSION_HEADER).unwrap(), "1");
+
+        let res = call_service(&app, TestRequest::get().uri("/snake").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(DEPRECATION_HEADER).unwrap(), "true");
+        assert_eq!(
+            res.headers().get(header::LINK).unwrap(),
+            "</api/v1/snake>; rel=\"successor-version\""
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use super::rate_limit::{RateLimited, VoteLimiter};
+
+use crate::game::movement::{OrderError, OrderMove};
+use crate::game::{Direction, Snapshot};
+use crate::metrics::{self, rejection};
+use std::net::IpAddr;
+use thiserror::Error;
+
+/// Why a move sent by a player was not counted
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MoveRejection {
+    #[error("Snake is moving {current}, turning {requested} would reverse it")]
+    Reverse {
+        current: Direction,
+        requested: Direction,
+    },
+
+    #[error(transparent)]
+    RateLimited(#[from] RateLimited),
+
+    #[error(transparent)]
+    Order(#[from] OrderError),
+}
+
+/// Votes for `direction` on behalf of `client`, the same way for HTTP and telnet players.
+/// Moves which end up not counted never take up the client's vote in the tick
+pub fn vote_move(
+    client: IpAddr,
+    direction: Direction,
+    snapshot: &Snapshot,
+    move_manager: &impl OrderMove,
+    limiter: &VoteLimiter,
+) -> Result<(), MoveRejection> {
+    // The game loop would drop it anyway
+    if direction == snapshot.direction.opposite() {
+        count_rejection(rejection::OPPOSITE_DIRECTION);
+        return Err(MoveRejection::Reverse {
+            current: snapshot.direction,
+            requested: direction,
+        });
+    }
+
+    // Planned moves resolve in the tick after the snapshot, like this one would
+    let voted = match move_manager.has_planned(client, snapshot.tick + 1) {
+        true => Err(limiter.voted_by_plan()),
+        false => limiter.try_vote(client, snapshot.tick),
+    };
+    if let Err(e) = voted {
+        count_rejection(rejection::RATE_LIMITED);
+        return Err(e.into());
+    }
+
+    // Queue rejections are counted by the issuer itself
+    move_manager.issue_move(direction).map_err(|e| {
+        limiter.refund(client, snapshot.tick);
+        e.into()
+    })
+}
+
+fn count_rejection(reason: &str) {
+    metrics::COMMANDS_REJECTED
+        .with_label_values(&[reason])
+        .inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{MoveCommandIssuer, Snapshots};
+    use pretty_assertions::assert_eq;
+    use std::net::Ipv4Addr;
+    use tokio::sync::mpsc;
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    #[test]
+    fn test_rejected_moves_keep_the_vote() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let move_manager = MoveCommandIssuer::from(sender);
+        let limiter = VoteLimiter::new(10.0);
+        let snapshot = Snapshots::default().load();
+        let current = snapshot.direction;
+        let vote = |direction| vote_move(CLIENT, direction, &snapshot, &move_manager, &limiter);
+
+        assert_eq!(
+            vote(current.opposite()),
+            Err(MoveRejection::Reverse {
+                current,
+                requested: current.opposite()
+            })
+        );
+        assert_eq!(vote(current), Ok(()));
+        assert!(matches!(
+            vote(current),
+            Err(MoveRejection::RateLimited(RateLimited::AlreadyVoted { .. }))
+        ));
+
+        limiter.refund(CLIENT, snapshot.tick);
+        assert_eq!(
+            vote(current),
+            Err(MoveRejection::Order(OrderError::QueueFull))
+        );
+        receiver.try_recv().unwrap();
+        assert_eq!(vote(current), Ok(()));
+    }
+}
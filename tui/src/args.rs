@@ -0,0 +1,136 @@
+use snake_client::DEFAULT_URL;
+
+use thiserror::Error;
+
+const FPS: f32 = 10.0;
+
+pub const USAGE: &str = "Usage: snake-tui [--url <URL>] [--offline [--fps <FPS>]]
+
+Options:
+  --url <URL>    Address of the snake server [env: SNAKE_URL] [default: http://localhost:8080]
+  --offline      Play locally, running the game inside the client instead of connecting to a server
+  --fps <FPS>    Frames per second of the offline game [default: 10]
+  -h, --help     Print this message";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    Online { url: String },
+    Offline { fps: f32 },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Play(Mode),
+    Help,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ArgsError {
+    #[error("Option `{0}` requires a value")]
+    MissingValue(String),
+
+    #[error("`{0}` is not a valid number of frames per second")]
+    InvalidFps(String),
+
+    #[error("Unknown argument `{0}`")]
+    Unknown(String),
+
+    #[error("`--url` cannot be used together with `--offline`")]
+    UrlInOfflineMode,
+}
+
+pub fn parse(
+    args: impl IntoIterator<Item = String>,
+    env_url: Option<String>,
+) -> Result<Command, ArgsError> {
+    let mut args = args.into_iter();
+    let mut url = None;
+    let mut fps = None;
+    let mut offline = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--offline" => offline = true,
+            "--url" => url = Some(args.next().ok_or(ArgsError::MissingValue(arg))?),
+            "--fps" => {
+                let value = args.next().ok_or(ArgsError::MissingValue(arg))?;
+                match value.parse::<f32>() {
+                    Ok(parsed) if parsed > 0.0 && parsed.is_finite() => fps = Some(parsed),
+                    _ => return Err(ArgsError::InvalidFps(value)),
+                }
+            }
+            _ => return Err(ArgsError::Unknown(arg)),
+        }
+    }
+
+    if offline {
+        if url.is_some() {
+            return Err(ArgsError::UrlInOfflineMode);
+        }
+        return Ok(Command::Play(Mode::Offline {
+            fps: fps.unwrap_or(FPS),
+        }));
+    }
+
+    Ok(Command::Play(Mode::Online {
+        url: url.or(env_url).unwrap_or_else(|| DEFAULT_URL.to_owned()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_default_is_online_with_default_url() {
+        assert_eq!(
+            parse(args(&[]), None),
+            Ok(Command::Play(Mode::Online {
+                url: DEFAULT_URL.to_owned()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_url_flag_wins_over_env() {
+        assert_eq!(
+            parse(
+                args(&["--url", "http://snake:9000"]),
+                Some("http://env:1".to_owned())
+            ),
+            Ok(Command::Play(Mode::Online {
+                url: "http://snake:9000".to_owned()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_offline_with_fps() {
+        assert_eq!(
+            parse(args(&["--offline", "--fps", "5"]), None),
+            Ok(Command::Play(Mode::Offline { fps: 5.0 }))
+        );
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert_eq!(
+            parse(args(&["--fps", "-1"]), None),
+            Err(ArgsError::InvalidFps("-1".to_owned()))
+        );
+        assert_eq!(
+            parse(args(&["--url"]), None),
+            Err(ArgsError::MissingValue("--url".to_owned()))
+        );
+        assert_eq!(
+            parse(args(&["--offline", "--url", "x"]), None),
+            Err(ArgsError::UrlInOfflineMode)
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use snake::game::{movement::OrderMove, spawn_game, GameHandles, GameMode};
+use snake_client::{blocking::Client, BoardState, Direction};
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const MIN_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+/// Pause between state requests while connected, about as long as a redraw of the screen
+const POLL_EVERY: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Connecting,
+    Connected,
+    Reconnecting { retry_in: Duration, error: String },
+    Offline,
+}
+
+pub trait Backend {
+    /// Latest known state of the board, `None` until the first one arrives
+    fn poll(&mut self) -> Option<&BoardState>;
+
+    fn issue_move(&mut self, direction: Direction);
+
+    fn status(&self) -> Status;
+
+    fn describe(&self) -> String;
+}
+
+/// Outcome of a state request made by the fetcher thread
+enum Fetched {
+    State(BoardState),
+    Failed { error: String, retry_in: Duration },
+}
+
+/// Plays against a snake server, reconnecting with exponential backoff whenever it goes away
+pub struct Online {
+    url: String,
+    moves_tx: Option<mpsc::Sender<Direction>>,
+    mover: Option<thread::JoinHandle<()>>,
+    states_rx: mpsc::Receiver<Fetched>,
+    /// Dropped to stop the fetcher thread, which waits on it between requests
+    stop_tx: Option<mpsc::Sender<()>>,
+    fetcher: Option<thread::JoinHandle<()>>,
+    state: Option<BoardState>,
+    status: Status,
+    retry_at: Option<Instant>,
+}
+
+impl Online {
+    pub fn new(url: String) -> Result<Self, reqwest::Error> {
+        let http = reqwest::blocking::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        let client = Client::with_http_client(http, url.clone());
+
+        // Moves are sent from a separate thread, so a slow server does not freeze the screen
+        let (moves_tx, moves_rx) = mpsc::channel::<Direction>();
+        let mover_client = client.clone();
+        let mover = thread::spawn(move || {
+            while let Ok(direction) = moves_rx.recv() {
+                // Connection problems are already reported by polling the board
+                let _ = mover_client.issue_move(direction);
+            }
+        });
+
+        // The board is fetched from another one for the same reason
+        let (states_tx, states_rx) = mpsc::channel();
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let fetcher = thread::spawn(move || fetch_states(client, states_tx, stop_rx));
+
+        Ok(Self {
+            url,
+            moves_tx: Some(moves_tx),
+            mover: Some(mover),
+            states_rx,
+            stop_tx: Some(stop_tx),
+            fetcher: Some(fetcher),
+            state: None,
+            status: Status::Connecting,
+            retry_at: None,
+        })
+    }
+}
+
+/// Keeps requesting the state of the board, backing off exponentially while the server is away
+fn fetch_states(client: Client, states_tx: mpsc::Sender<Fetched>, stop_rx: mpsc::Receiver<()>) {
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let (fetched, wait) = match client.state() {
+            Ok(state) => {
+                backoff = MIN_BACKOFF;
+                (Fetched::State(state), POLL_EVERY)
+            }
+            Err(e) => {
+                let retry_in = backoff;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                let error = e.to_string();
+                (Fetched::Failed { error, retry_in }, retry_in)
+            }
+        };
+        if states_tx.send(fetched).is_err() {
+            return;
+        }
+        // Anything but a timeout means the backend is gone
+        if !matches!(stop_rx.recv_timeout(wait), Err(RecvTimeoutError::Timeout)) {
+            return;
+        }
+    }
+}
+
+impl Backend for Online {
+    fn poll(&mut self) -> Option<&BoardState> {
+        for fetched in self.states_rx.try_iter() {
+            match fetched {
+                Fetched::State(state) => {
+                    self.state = Some(state);
+                    self.status = Status::Connected;
+                    self.retry_at = None;
+                }
+                Fetched::Failed { error, retry_in } => {
+                    self.status = Status::Reconnecting { retry_in, error };
+                    self.retry_at = Some(Instant::now() + retry_in);
+                }
+            }
+        }
+        if let (Some(retry_at), Status::Reconnecting { retry_in, .. }) =
+            (self.retry_at, &mut self.status)
+        {
+            *retry_in = retry_at.saturating_duration_since(Instant::now());
+        }
+
+        self.state.as_ref()
+    }
+
+    fn issue_move(&mut self, direction: Direction) {
+        if let Some(moves_tx) = &self.moves_tx {
+            let _ = moves_tx.send(direction);
+        }
+    }
+
+    fn status(&self) -> Status {
+        self.status.clone()
+    }
+
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+}
+
+impl Drop for Online {
+    fn drop(&mut self) {
+        // Closing the channels lets both threads finish, the fetcher once its request is done
+        self.moves_tx.take();
+        self.stop_tx.take();
+        if let Some(mover) = self.mover.take() {
+            let _ = mover.join();
+        }
+        if let Some(fetcher) = self.fetcher.take() {
+            let _ = fetcher.join();
+        }
+    }
+}
+
+/// Runs the game engine in-process, no server needed
+pub struct Offline {
+    runtime: Option<Runtime>,
+    handles: GameHandles,
+    state: Option<BoardState>,
+}
+
+impl Offline {
+    pub fn new(fps: f32) -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+
+        let handles = {
+            let _guard = runtime.enter();
+            let (handles, _game_loop_task) = spawn_game(fps, GameMode::default());
+            handles
+        };
+
+        Ok(Self {
+            runtime: Some(runtime),
+            handles,
+            state: None,
+        })
+    }
+}
+
+impl Backend for Offline {
+    fn poll(&mut self) -> Option<&BoardState> {
+        let state = BoardState::from(&self.handles.snapshots.load().board);
+        self.state = Some(state);
+        self.state.as_ref()
+    }
+
+    fn issue_move(&mut self, direction: Direction) {
+        // The channel is only full if the game is not consuming moves, dropping one is fine then
+        let _ = self
+            .handles
+            .order_move
+            .read()
+            .unwrap()
+            .issue_move(direction);
+    }
+
+    fn status(&self) -> Status {
+        Status::Offline
+    }
+
+    fn describe(&self) -> String {
+        "local game".to_owned()
+    }
+}
+
+impl Drop for Offline {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unreachable_server_does_not_block_polling() {
+        // Nothing listens on the discard port, so connections are refused right away
+        let mut backend = Online::new("http://127.0.0.1:9".to_owned()).unwrap();
+
+        let polled = Instant::now();
+        assert_eq!(backend.poll(), None);
+        assert!(polled.elapsed() < REQUEST_TIMEOUT);
+
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        while backend.status() == Status::Connecting && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+            backend.poll();
+        }
+        assert!(matches!(backend.status(), Status::Reconnecting { .. }));
+    }
+}
@@ -0,0 +1,90 @@
+mod args;
+mod backend;
+mod ui;
+
+use args::{Command, Mode};
+use backend::{Backend, Offline, Online};
+
+use snake_client::Direction;
+use std::io::{self, Write};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::IntoAlternateScreen;
+
+const REDRAW_EVERY: Duration = Duration::from_millis(50);
+
+fn direction_of(key: Key) -> Option<Direction> {
+    match key {
+        Key::Up | Key::Char('k') | Key::Char('w') => Some(Direction::Up),
+        Key::Down | Key::Char('j') | Key::Char('s') => Some(Direction::Down),
+        Key::Left | Key::Char('h') | Key::Char('a') => Some(Direction::Left),
+        Key::Right | Key::Char('l') | Key::Char('d') => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn play(backend: &mut dyn Backend) -> io::Result<()> {
+    let screen = io::stdout().into_raw_mode()?.into_alternate_screen()?;
+    let mut screen = termion::cursor::HideCursor::from(screen);
+    let mut keys = termion::async_stdin().keys();
+
+    loop {
+        let frame_started = Instant::now();
+
+        // Drain every key pressed since the last frame
+        for key in keys.by_ref() {
+            match key? {
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') => return Ok(()),
+                key => {
+                    if let Some(direction) = direction_of(key) {
+                        backend.issue_move(direction);
+                    }
+                }
+            }
+        }
+
+        let server = backend.describe();
+        let state = backend.poll().cloned();
+        ui::draw(&mut screen, state.as_ref(), &backend.status(), &server)?;
+
+        thread::sleep(REDRAW_EVERY.saturating_sub(frame_started.elapsed()));
+    }
+}
+
+fn run(mode: Mode) -> Result<(), String> {
+    let mut backend: Box<dyn Backend> = match mode {
+        Mode::Online { url } => {
+            Box::new(Online::new(url).map_err(|e| format!("Unable to create HTTP client: {e}"))?)
+        }
+        Mode::Offline { fps } => {
+            Box::new(Offline::new(fps).map_err(|e| format!("Unable to start local game: {e}"))?)
+        }
+    };
+
+    play(backend.as_mut()).map_err(|e| format!("Terminal error: {e}"))
+}
+
+fn main() -> ExitCode {
+    let command = args::parse(std::env::args().skip(1), std::env::var("SNAKE_URL").ok());
+
+    let result = match command {
+        Ok(Command::Help) => {
+            println!("{}", args::USAGE);
+            Ok(())
+        }
+        Ok(Command::Play(mode)) => run(mode),
+        Err(e) => Err(format!("{e}\n\n{}", args::USAGE)),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let _ = writeln!(io::stderr(), "{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use super::backend::Status;
+
+use snake_client::{BoardState, Edge};
+use std::io::{self, Write};
+use termion::{clear, color, cursor, style};
+
+const KEY_HELP: [&str; 3] = ["arrows/hjkl/wasd - steer", "q/Esc - quit", ""];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Snake,
+    Head,
+    Fruit,
+    Wall,
+    /// Either end of the pair with the given number
+    Portal(u8),
+}
+
+fn cells(state: &BoardState) -> Vec<Vec<Cell>> {
+    let mut grid = vec![vec![Cell::Empty; state.width as usize]; state.height as usize];
+    let mut set = |x: u16, y: u16, cell| {
+        if let Some(row) = grid.get_mut(y as usize) {
+            if let Some(c) = row.get_mut(x as usize) {
+                *c = cell;
+            }
+        }
+    };
+
+    state.walls.iter().for_each(|p| set(p.x, p.y, Cell::Wall));
+    for (pair, portal) in state.portals.iter().enumerate() {
+        for end in [portal.entrance, portal.exit] {
+            set(end.x, end.y, Cell::Portal(pair as u8));
+        }
+    }
+    state.fruits.iter().for_each(|p| set(p.x, p.y, Cell::Fruit));
+    state.snake.iter().for_each(|p| set(p.x, p.y, Cell::Snake));
+    if let Some(head) = state.head {
+        set(head.x, head.y, Cell::Head);
+    }
+
+    grid
+}
+
+/// Border drawn along edges crossed moving left or right, and up or down, the same as on the
+/// server's text board
+fn edge_chars(edge: Edge) -> (char, char) {
+    match edge {
+        Edge::Wrap => ('│', '─'),
+        Edge::Mirror => ('║', '═'),
+        Edge::Wall => ('┃', '━'),
+    }
+}
+
+/// Board with its walls, one string per screen line, coloured with terminal escapes
+pub fn board_lines(state: &BoardState) -> Vec<String> {
+    let wall = |s: String| format!("{}{s}{}", color::Fg(color::Blue), color::Fg(color::Reset));
+    let (side, _) = edge_chars(state.topology.horizontal);
+    let (_, top) = edge_chars(state.topology.vertical);
+    let horizontal = top.to_string().repeat(state.width as usize);
+
+    let mut lines = vec![wall(format!("┌{horizontal}┐"))];
+    for row in cells(state) {
+        let mut line = wall(side.to_string());
+        for cell in row {
+            match cell {
+                Cell::Empty => line.push(' '),
+                Cell::Snake => line.push_str(&format!(
+                    "{}#{}",
+                    color::Fg(color::Green),
+                    color::Fg(color::Reset)
+                )),
+                Cell::Head => line.push_str(&format!(
+                    "{}{}@{}{}",
+                    style::Bold,
+                    color::Fg(color::LightGreen),
+                    color::Fg(color::Reset),
+                    style::Reset
+                )),
+                Cell::Fruit => line.push_str(&format!(
+                    "{}O{}",
+                    color::Fg(color::LightRed),
+                    color::Fg(color::Reset)
+                )),
+                Cell::Wall => line.push_str(&wall("█".to_owned())),
+                Cell::Portal(pair) => line.push_str(&format!(
+                    "{}{}{}",
+                    color::Fg(color::Magenta),
+                    (b'A' + pair % 26) as char,
+                    color::Fg(color::Reset)
+                )),
+            }
+        }
+        line.push_str(&wall(side.to_string()));
+        lines.push(line);
+    }
+    lines.push(wall(format!("└{horizontal}┘")));
+
+    lines
+}
+
+pub fn panel_lines(state: Option<&BoardState>, status: &Status, server: &str) -> Vec<String> {
+    let status = match status {
+        Status::Connecting => "connecting...".to_owned(),
+        Status::Connected => "connected".to_owned(),
+        Status::Reconnecting { retry_in, error } => {
+            format!("retrying in {:.1}s ({error})", retry_in.as_secs_f32())
+        }
+        Status::Offline => "offline".to_owned(),
+    };
+
+    let mut lines = vec![
+        format!("Score:  {}", state.map_or(0, |s| s.score)),
+        format!(
+            "Length: {}",
+            state.map_or(0, |s| s.snake.len() + s.head.is_some() as usize)
+        ),
+    ];
+    if let Some(level) = state.and_then(|s| s.level.as_ref()) {
+        lines.push(format!(
+            "Level:  {}/{} {}",
+            level.number, level.stages, level.name
+        ));
+    }
+    lines.extend([
+        String::new(),
+        format!("Game:   {server}"),
+        format!("Status: {status}"),
+        String::new(),
+    ]);
+    lines.extend(KEY_HELP.iter().map(|s| s.to_string()));
+
+    lines
+}
+
+/// Redraws the whole screen in place, line by line, without clearing it first to avoid flicker
+pub fn draw(
+    out: &mut impl Write,
+    state: Option<&BoardState>,
+    status: &Status,
+    server: &str,
+) -> io::Result<()> {
+    let board = state.map(board_lines).unwrap_or_else(|| {
+        vec![format!(
+            "{}Waiting for the first frame...{}",
+            style::Italic,
+            style::Reset
+        )]
+    });
+    let panel = panel_lines(state, status, server);
+
+    for row in 0..board.len().max(panel.len()) {
+        write!(out, "{}", cursor::Goto(1, row as u16 + 1))?;
+        match board.get(row) {
+            Some(line) => write!(out, "{line}  ")?,
+            // Keep panel aligned with the right edge of the board
+            None => write!(
+                out,
+                "{}",
+                " ".repeat(state.map_or(0, |s| s.width as usize + 2) + 2)
+            )?,
+        }
+        write!(
+            out,
+            "{}{}",
+            panel.get(row).map(String::as_str).unwrap_or(""),
+            clear::UntilNewline
+        )?;
+    }
+    write!(out, "{}", clear::AfterCursor)?;
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use snake_client::{Point, Portal, Topology};
+
+    fn state() -> BoardState {
+        BoardState {
+            width: 3,
+            height: 2,
+            topology: Topology::default(),
+            score: 7,
+            head: Some(Point { x: 0, y: 0 }),
+            snake: vec![Point { x: 1, y: 0 }],
+            fruits: vec![Point { x: 2, y: 1 }],
+            walls: vec![Point { x: 1, y: 1 }],
+            portals: vec![Portal {
+                entrance: Point { x: 2, y: 0 },
+                exit: Point { x: 0, y: 1 },
+            }],
+            level: None,
+        }
+    }
+
+    #[test]
+    fn test_cells_from_state() {
+        assert_eq!(
+            cells(&state()),
+            vec![
+                vec![Cell::Head, Cell::Snake, Cell::Portal(0)],
+                vec![Cell::Portal(0), Cell::Wall, Cell::Fruit],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_board_lines_include_walls() {
+        let lines = board_lines(&state());
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("┌───┐"));
+        assert!(lines[3].contains("└───┘"));
+    }
+
+    #[test]
+    fn test_board_lines_follow_topology() {
+        let mut state = state();
+        state.topology = Topology::MOBIUS;
+
+        let lines = board_lines(&state);
+
+        assert!(lines[0].contains("┌━━━┐"));
+        assert!(lines[1].contains('║'));
+        assert!(!lines[1].contains('│'));
+    }
+
+    #[test]
+    fn test_panel_shows_score_and_length() {
+        let lines = panel_lines(Some(&state()), &Status::Offline, "local game");
+
+        assert_eq!(lines[0], "Score:  7");
+        assert_eq!(lines[1], "Length: 2");
+        assert_eq!(lines[4], "Status: offline");
+    }
+}